@@ -0,0 +1,16 @@
+use std::process::Command;
+
+// plugins built with another compiler lay out `dyn Plugin` differently, the
+// version is compared before one is loaded
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .unwrap_or_default();
+    println!("cargo:rustc-env=APP_BASE_RUSTC_VERSION={}", version.trim());
+    println!("cargo:rerun-if-env-changed=RUSTC");
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,267 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+};
+
+use winit::{
+    error::ExternalError,
+    event::WindowEvent,
+    event_loop::{ActiveEventLoop, ControlFlow},
+    window::{Window, WindowAttributes},
+};
+
+use crate::{App, ApplicationEvent};
+
+/// The parts of the running event loop window event systems may use.
+pub trait EventLoopContext {
+    fn create_window(&self, attributes: WindowAttributes) -> Result<Window, ExternalError>;
+    fn exit(&self);
+    fn exiting(&self) -> bool;
+    fn set_control_flow(&self, control_flow: ControlFlow);
+}
+
+impl EventLoopContext for ActiveEventLoop {
+    fn create_window(&self, attributes: WindowAttributes) -> Result<Window, ExternalError> {
+        ActiveEventLoop::create_window(self, attributes).map_err(ExternalError::Os)
+    }
+
+    fn exit(&self) {
+        ActiveEventLoop::exit(self);
+    }
+
+    fn exiting(&self) -> bool {
+        ActiveEventLoop::exiting(self)
+    }
+
+    fn set_control_flow(&self, control_flow: ControlFlow) {
+        ActiveEventLoop::set_control_flow(self, control_flow);
+    }
+}
+
+/// While present, live events matching `filter` are dropped before they reach
+/// any system, so a replay can stand in for that part of the input.
+pub struct SuppressLiveInput {
+    pub filter: fn(&WindowEvent) -> bool,
+}
+
+impl Default for SuppressLiveInput {
+    fn default() -> Self {
+        SuppressLiveInput { filter: is_input_event }
+    }
+}
+
+/// Events handed to window event systems with the real event loop during the
+/// next pump, as if winit had sent them.
+#[derive(Default)]
+pub struct InjectedEvents {
+    events: VecDeque<ApplicationEvent>,
+}
+
+impl InjectedEvents {
+    pub fn push(&mut self, event: ApplicationEvent) {
+        self.events.push_back(event);
+    }
+
+    pub fn pop(&mut self) -> Option<ApplicationEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+pub fn is_input_event(event: &WindowEvent) -> bool {
+    matches!(
+        event,
+        WindowEvent::KeyboardInput { .. }
+            | WindowEvent::ModifiersChanged(_)
+            | WindowEvent::Ime(_)
+            | WindowEvent::CursorMoved { .. }
+            | WindowEvent::CursorEntered { .. }
+            | WindowEvent::CursorLeft { .. }
+            | WindowEvent::MouseWheel { .. }
+            | WindowEvent::MouseInput { .. }
+            | WindowEvent::Touch(_)
+            | WindowEvent::PinchGesture { .. }
+            | WindowEvent::PanGesture { .. }
+            | WindowEvent::DoubleTapGesture { .. }
+            | WindowEvent::RotationGesture { .. }
+            | WindowEvent::TouchpadPressure { .. }
+            | WindowEvent::AxisMotion { .. }
+    )
+}
+
+pub type WindowFactory = Box<dyn Fn(&WindowAttributes) -> Result<Window, ExternalError>>;
+
+/// Records what systems ask of the event loop instead of talking to winit.
+/// winit windows can't exist without a real event loop, so window creation
+/// fails with `ExternalError::Ignored` unless a factory is set, for example
+/// one forwarding to a loop driven with `pump_app_events`.
+pub struct MockEventLoop {
+    requested_windows: RefCell<Vec<WindowAttributes>>,
+    window_factory: Option<WindowFactory>,
+    exit: Cell<bool>,
+    control_flow: Cell<ControlFlow>,
+}
+
+impl Default for MockEventLoop {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockEventLoop {
+    pub fn new() -> Self {
+        MockEventLoop {
+            requested_windows: RefCell::new(Vec::new()),
+            window_factory: None,
+            exit: Cell::new(false),
+            control_flow: Cell::new(ControlFlow::default()),
+        }
+    }
+
+    pub fn with_window_factory<F>(mut self, factory: F) -> Self
+    where
+        F: Fn(&WindowAttributes) -> Result<Window, ExternalError> + 'static,
+    {
+        self.window_factory = Some(Box::new(factory));
+        self
+    }
+
+    pub fn requested_windows(&self) -> Vec<WindowAttributes> {
+        self.requested_windows.borrow().clone()
+    }
+
+    pub fn exit_requested(&self) -> bool {
+        self.exit.get()
+    }
+
+    pub fn control_flow(&self) -> ControlFlow {
+        self.control_flow.get()
+    }
+
+    pub fn send(&self, app: &mut App, event: ApplicationEvent) {
+        app.run_event(event, self);
+    }
+
+    pub fn send_all<I: IntoIterator<Item = ApplicationEvent>>(&self, app: &mut App, events: I) {
+        for event in events {
+            self.send(app, event);
+        }
+    }
+}
+
+impl EventLoopContext for MockEventLoop {
+    fn create_window(&self, attributes: WindowAttributes) -> Result<Window, ExternalError> {
+        let created = match &self.window_factory {
+            Some(factory) => factory(&attributes),
+            None => Err(ExternalError::Ignored),
+        };
+        self.requested_windows.borrow_mut().push(attributes);
+        created
+    }
+
+    fn exit(&self) {
+        self.exit.set(true);
+    }
+
+    fn exiting(&self) -> bool {
+        self.exit.get()
+    }
+
+    fn set_control_flow(&self, control_flow: ControlFlow) {
+        self.control_flow.set(control_flow);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use winit::window::WindowId;
+
+    use super::*;
+    use crate::{events::Events, storage::ResMut};
+
+    #[derive(Default)]
+    struct Created(Vec<String>);
+
+    fn open_window(event: ApplicationEvent, event_loop: &dyn EventLoopContext, mut created: ResMut<Created>) {
+        match event {
+            ApplicationEvent::Resumed => {
+                let attributes = WindowAttributes::default().with_title("mock");
+                if let Err(e) = event_loop.create_window(attributes) {
+                    created.0.push(e.to_string());
+                }
+                event_loop.set_control_flow(ControlFlow::Poll);
+            }
+            ApplicationEvent::Quit => event_loop.exit(),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn records_requests_of_window_event_systems() {
+        let mut app = App::new();
+        app.init_resource::<Created>();
+        app.add_window_event_system(open_window);
+        let event_loop = MockEventLoop::default();
+
+        event_loop.send(&mut app, ApplicationEvent::Resumed);
+        assert_eq!(event_loop.requested_windows().len(), 1);
+        assert_eq!(event_loop.requested_windows()[0].title, "mock");
+        assert_eq!(event_loop.control_flow(), ControlFlow::Poll);
+        assert!(!event_loop.exit_requested());
+
+        event_loop.send(&mut app, ApplicationEvent::Quit);
+        assert!(event_loop.exit_requested());
+        assert!(event_loop.exiting());
+        assert_eq!(app.scheduler.get_resource::<Created>().unwrap().0.len(), 1);
+    }
+
+    #[test]
+    fn events_reach_the_event_queue() {
+        let mut app = App::new();
+        let event_loop = MockEventLoop::new();
+        let id = WindowId::from(7);
+        event_loop.send_all(
+            &mut app,
+            [
+                ApplicationEvent::Resumed,
+                ApplicationEvent::WindowEvent {
+                    id,
+                    event: WindowEvent::CloseRequested,
+                },
+            ],
+        );
+        let events = app.scheduler.get_resource::<Events<ApplicationEvent>>().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(
+            events.iter().last(),
+            Some(ApplicationEvent::WindowEvent { event: WindowEvent::CloseRequested, .. })
+        ));
+    }
+
+    #[test]
+    fn window_factory_creates_windows() {
+        let calls = Rc::new(Cell::new(0));
+        let counted = calls.clone();
+        let event_loop = MockEventLoop::new().with_window_factory(move |attributes| {
+            counted.set(counted.get() + 1);
+            assert_eq!(attributes.title, "mock");
+            Err(ExternalError::Ignored)
+        });
+        let mut app = App::new();
+        app.init_resource::<Created>();
+        app.add_window_event_system(open_window);
+        event_loop.send(&mut app, ApplicationEvent::Resumed);
+        assert_eq!(calls.get(), 1);
+        assert_eq!(event_loop.requested_windows().len(), 1);
+        assert_eq!(app.scheduler.get_resource::<Created>().unwrap().0.len(), 1);
+    }
+}
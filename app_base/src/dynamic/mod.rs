@@ -0,0 +1,214 @@
+use std::{
+    any::TypeId,
+    collections::BTreeSet,
+    ffi::{CStr, c_char, c_void},
+    fmt::Display,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use libloading::Library;
+
+use crate::{Plugin, system::{commands::Command, scheduler::Scheduler}};
+
+/// Bumped whenever [`PluginDeclaration`] or the way plugins are created changes.
+pub const PLUGIN_ABI_VERSION: u32 = 2;
+pub const APP_BASE_VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+/// `rustc --version` of the compiler app_base was built with.
+pub const RUSTC_VERSION: &str = concat!(env!("APP_BASE_RUSTC_VERSION"), "\0");
+pub const PLUGIN_ENTRY_SYMBOL: &[u8] = b"app_plugin_entry\0";
+
+/// Tells apart builds of app_base, two of them only share resources when their
+/// type ids agree, which takes the same sources, features and compiler.
+pub fn build_id() -> u64 {
+    let mut hasher = DefaultHasher::new();
+    TypeId::of::<crate::App>().hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returned by the `app_plugin_entry` symbol of a plugin library, see [`export_plugin!`].
+///
+/// Only the declaration crosses the C boundary, the plugin itself is a Rust
+/// trait object with no stable layout. It is only taken once the compiler and
+/// `app_base` versions of the plugin match the host's.
+#[repr(C)]
+pub struct PluginDeclaration {
+    pub abi_version: u32,
+    pub app_base_version: *const c_char,
+    pub rustc_version: *const c_char,
+    pub build_id: u64,
+    pub name: *const c_char,
+    /// Returns a `Box<Box<dyn Plugin>>` turned into a raw pointer.
+    pub create: extern "C" fn() -> *mut c_void,
+}
+
+pub type PluginEntry = extern "C" fn() -> PluginDeclaration;
+
+/// Exports a plugin from a `cdylib` crate so [`crate::App::load_plugin`] can find it.
+#[macro_export]
+macro_rules! export_plugin {
+    ($name:literal, $create:expr) => {
+        #[unsafe(no_mangle)]
+        pub extern "C" fn app_plugin_entry() -> $crate::dynamic::PluginDeclaration {
+            extern "C" fn create() -> *mut ::std::ffi::c_void {
+                let plugin: ::std::boxed::Box<dyn $crate::Plugin> = ::std::boxed::Box::new($create);
+                ::std::boxed::Box::into_raw(::std::boxed::Box::new(plugin)) as *mut ::std::ffi::c_void
+            }
+            $crate::dynamic::PluginDeclaration {
+                abi_version: $crate::dynamic::PLUGIN_ABI_VERSION,
+                app_base_version: $crate::dynamic::APP_BASE_VERSION.as_ptr() as *const ::std::ffi::c_char,
+                rustc_version: $crate::dynamic::RUSTC_VERSION.as_ptr() as *const ::std::ffi::c_char,
+                build_id: $crate::dynamic::build_id(),
+                name: concat!($name, "\0").as_ptr() as *const ::std::ffi::c_char,
+                create,
+            }
+        }
+    };
+}
+
+#[derive(Debug)]
+pub enum PluginLoadError {
+    Library(PathBuf, libloading::Error),
+    AbiVersion { expected: u32, found: u32 },
+    AppBaseVersion { expected: String, found: String },
+    RustcVersion { expected: String, found: String },
+    /// Same versions, but app_base was built with other features or flags.
+    BuildMismatch,
+    AlreadyLoaded(String),
+    NotLoaded(String),
+    /// The plugin's code may still run or is still referenced, unloading it now would leave dangling code.
+    InUse { name: String, reason: String },
+}
+
+impl Display for PluginLoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginLoadError::Library(path, e) => write!(f, "failed to load plugin {}: {}", path.display(), e),
+            PluginLoadError::AbiVersion { expected, found } => {
+                write!(f, "plugin abi version {} does not match {}", found, expected)
+            }
+            PluginLoadError::AppBaseVersion { expected, found } => {
+                write!(f, "plugin was built against app_base {} but the host uses {}", found, expected)
+            }
+            PluginLoadError::RustcVersion { expected, found } => {
+                write!(f, "plugin was built with {} but the host with {}", found, expected)
+            }
+            PluginLoadError::BuildMismatch => {
+                write!(f, "plugin was built against another build of app_base, check its features")
+            }
+            PluginLoadError::AlreadyLoaded(name) => write!(f, "plugin {} is already loaded", name),
+            PluginLoadError::NotLoaded(name) => write!(f, "plugin {} is not loaded", name),
+            PluginLoadError::InUse { name, reason } => write!(f, "plugin {} can't be unloaded, {}", name, reason),
+        }
+    }
+}
+
+impl std::error::Error for PluginLoadError {}
+
+fn c_str(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    // the pointers come from string literals in the plugin, valid while it is loaded
+    unsafe { CStr::from_ptr(ptr) }.to_string_lossy().into_owned()
+}
+
+// systems and resources are tagged with a &'static str and the library's own
+// strings go away on unload, so every name is copied once and reused on reload
+fn intern(name: String) -> &'static str {
+    static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+    let mut names = NAMES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(interned) = names.get(name.as_str()) {
+        return interned;
+    }
+    let interned: &'static str = Box::leak(name.into_boxed_str());
+    names.insert(interned);
+    interned
+}
+
+fn check_version(expected: &str, found: *const c_char, error: fn(String, String) -> PluginLoadError) -> Result<(), PluginLoadError> {
+    let expected = expected.trim_end_matches('\0');
+    let found = c_str(found);
+    if found != expected {
+        return Err(error(expected.to_string(), found));
+    }
+    Ok(())
+}
+
+pub struct DynamicPlugin {
+    name: &'static str,
+    path: PathBuf,
+    // dropped before the library, its vtable lives in there
+    plugin: Box<dyn Plugin>,
+    _library: Library,
+}
+
+impl DynamicPlugin {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, PluginLoadError> {
+        let path = path.as_ref().to_path_buf();
+        let library = unsafe { Library::new(&path) }
+            .map_err(|e| PluginLoadError::Library(path.clone(), e))?;
+        let entry = unsafe { library.get::<PluginEntry>(PLUGIN_ENTRY_SYMBOL) }
+            .map_err(|e| PluginLoadError::Library(path.clone(), e))?;
+        let declaration = entry();
+        if declaration.abi_version != PLUGIN_ABI_VERSION {
+            return Err(PluginLoadError::AbiVersion {
+                expected: PLUGIN_ABI_VERSION,
+                found: declaration.abi_version,
+            });
+        }
+        check_version(APP_BASE_VERSION, declaration.app_base_version, |expected, found| {
+            PluginLoadError::AppBaseVersion { expected, found }
+        })?;
+        check_version(RUSTC_VERSION, declaration.rustc_version, |expected, found| {
+            PluginLoadError::RustcVersion { expected, found }
+        })?;
+        if declaration.build_id != build_id() {
+            return Err(PluginLoadError::BuildMismatch);
+        }
+        let name = intern(c_str(declaration.name));
+        // same compiler and app_base, so the trait object matches the host's
+        let plugin = unsafe { *Box::from_raw((declaration.create)() as *mut Box<dyn Plugin>) };
+        Ok(DynamicPlugin {
+            name,
+            path,
+            plugin,
+            _library: library,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn plugin(&self) -> &dyn Plugin {
+        self.plugin.as_ref()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PluginRequest {
+    Load(PathBuf),
+    Unload(String),
+    Reload(String),
+}
+
+/// Load requests from systems, the app handles them between frames.
+#[derive(Default)]
+pub struct PluginRequests {
+    pub requests: Vec<PluginRequest>,
+}
+
+impl Command for PluginRequest {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.init_shared_resource::<PluginRequests>();
+        if let Some(mut requests) = scheduler.get_resource_mut::<PluginRequests>() {
+            requests.requests.push(self);
+        }
+    }
+}
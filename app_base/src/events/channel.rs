@@ -0,0 +1,286 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use tokio::sync::{
+    Notify,
+    broadcast::error::{RecvError, SendError, TryRecvError},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Evict the oldest unread event, slow readers get `Lagged`.
+    DropOldest,
+    /// Reject the event being sent, `send` returns `Ok(0)`.
+    DropNewest,
+    /// Keep unread events beyond the capacity, up to `max_capacity`. Past that
+    /// the oldest unread event is evicted like with `DropOldest`.
+    Grow { max_capacity: usize },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EventConfig {
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+impl EventConfig {
+    pub fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        EventConfig {
+            capacity: capacity.max(1),
+            policy,
+        }
+    }
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        EventConfig::new(100, OverflowPolicy::DropOldest)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventStats {
+    /// Events queued for at least one reader.
+    pub sent: u64,
+    /// Events evicted or rejected before every reader saw them, each counted once.
+    pub dropped: u64,
+    /// How often a reader got `Lagged` because events it hadn't read were
+    /// evicted. The evicted events themselves are counted in `dropped`.
+    pub lagged: u64,
+}
+
+struct ChannelState<T> {
+    buffer: VecDeque<T>,
+    // sequence number of buffer[0]
+    head: u64,
+    readers: HashMap<u64, u64>,
+    next_reader: u64,
+    config: EventConfig,
+    stats: EventStats,
+}
+
+impl<T> ChannelState<T> {
+    fn tail(&self) -> u64 {
+        self.head + self.buffer.len() as u64
+    }
+
+    // drops events every reader has already seen
+    fn trim(&mut self) {
+        let min = self.readers.values().copied().min().unwrap_or(self.tail());
+        while self.head < min && self.buffer.pop_front().is_some() {
+            self.head += 1;
+        }
+    }
+}
+
+pub struct EventChannel<T> {
+    state: Mutex<ChannelState<T>>,
+    notify: Notify,
+}
+
+impl<T: Clone> EventChannel<T> {
+    pub fn new(config: EventConfig) -> Self {
+        EventChannel {
+            state: Mutex::new(ChannelState {
+                buffer: VecDeque::with_capacity(config.capacity),
+                head: 0,
+                readers: HashMap::new(),
+                next_reader: 0,
+                config,
+                stats: EventStats::default(),
+            }),
+            notify: Notify::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, ChannelState<T>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the number of readers the event was queued for, 0 when `DropNewest`
+    /// rejected it. Like the tokio broadcast channel, sending without readers fails
+    /// and hands the event back.
+    pub fn send(&self, event: T) -> Result<usize, SendError<T>> {
+        let mut state = self.lock();
+        if state.readers.is_empty() {
+            return Err(SendError(event));
+        }
+        let limit = match state.config.policy {
+            OverflowPolicy::Grow { max_capacity } => max_capacity.max(state.config.capacity),
+            _ => state.config.capacity,
+        };
+        if state.buffer.len() >= limit {
+            if state.config.policy == OverflowPolicy::DropNewest {
+                state.stats.dropped += 1;
+                return Ok(0);
+            }
+            state.buffer.pop_front();
+            state.head += 1;
+            state.stats.dropped += 1;
+        }
+        state.stats.sent += 1;
+        state.buffer.push_back(event);
+        let readers = state.readers.len();
+        drop(state);
+        self.notify.notify_waiters();
+        Ok(readers)
+    }
+
+    fn subscribe(&self) -> u64 {
+        let mut state = self.lock();
+        let id = state.next_reader;
+        state.next_reader += 1;
+        let tail = state.tail();
+        state.readers.insert(id, tail);
+        id
+    }
+
+    fn unsubscribe(&self, reader: u64) {
+        let mut state = self.lock();
+        state.readers.remove(&reader);
+        state.trim();
+    }
+
+    fn try_recv(&self, reader: u64) -> Result<T, TryRecvError> {
+        let mut state = self.lock();
+        let Some(cursor) = state.readers.get(&reader).copied() else {
+            return Err(TryRecvError::Closed);
+        };
+        if cursor < state.head {
+            let missed = state.head - cursor;
+            let head = state.head;
+            state.readers.insert(reader, head);
+            state.stats.lagged += 1;
+            return Err(TryRecvError::Lagged(missed));
+        }
+        if cursor == state.tail() {
+            return Err(TryRecvError::Empty);
+        }
+        let event = state.buffer[(cursor - state.head) as usize].clone();
+        state.readers.insert(reader, cursor + 1);
+        state.trim();
+        Ok(event)
+    }
+
+    pub fn config(&self) -> EventConfig {
+        self.lock().config
+    }
+
+    pub fn set_config(&self, config: EventConfig) {
+        self.lock().config = config;
+    }
+
+    pub fn stats(&self) -> EventStats {
+        self.lock().stats
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().buffer.is_empty()
+    }
+
+    pub fn reader_count(&self) -> usize {
+        self.lock().readers.len()
+    }
+}
+
+pub struct ChannelReader<T: Clone> {
+    channel: Arc<EventChannel<T>>,
+    id: u64,
+    // shared readers don't own the subscription and leave it alive when dropped
+    owned: bool,
+}
+
+impl<T: Clone> ChannelReader<T> {
+    pub fn new(channel: Arc<EventChannel<T>>) -> Self {
+        let id = channel.subscribe();
+        ChannelReader {
+            channel,
+            id,
+            owned: true,
+        }
+    }
+
+    pub fn share(&self) -> Self {
+        ChannelReader {
+            channel: self.channel.clone(),
+            id: self.id,
+            owned: false,
+        }
+    }
+
+    pub fn resubscribe(&self) -> Self {
+        Self::new(self.channel.clone())
+    }
+
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        self.channel.try_recv(self.id)
+    }
+
+    pub async fn recv(&mut self) -> Result<T, RecvError> {
+        let channel = self.channel.clone();
+        loop {
+            let mut notified = std::pin::pin!(channel.notify.notified());
+            // register before checking so a send in between still wakes us
+            notified.as_mut().enable();
+            match channel.try_recv(self.id) {
+                Ok(event) => return Ok(event),
+                Err(TryRecvError::Lagged(missed)) => return Err(RecvError::Lagged(missed)),
+                Err(TryRecvError::Closed) => return Err(RecvError::Closed),
+                Err(TryRecvError::Empty) => notified.await,
+            }
+        }
+    }
+
+    pub fn channel(&self) -> &Arc<EventChannel<T>> {
+        &self.channel
+    }
+}
+
+impl<T: Clone> Drop for ChannelReader<T> {
+    fn drop(&mut self) {
+        if self.owned {
+            self.channel.unsubscribe(self.id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_evictions_once() {
+        let channel = Arc::new(EventChannel::new(EventConfig::new(2, OverflowPolicy::DropOldest)));
+        let mut first = ChannelReader::new(channel.clone());
+        let mut second = ChannelReader::new(channel.clone());
+        for event in 0..5 {
+            assert_eq!(channel.send(event).ok(), Some(2));
+        }
+        assert!(matches!(first.try_recv(), Err(TryRecvError::Lagged(3))));
+        assert!(matches!(second.try_recv(), Err(TryRecvError::Lagged(3))));
+        assert_eq!(first.try_recv().ok(), Some(3));
+        assert_eq!(channel.stats(), EventStats {
+            sent: 5,
+            dropped: 3,
+            lagged: 2,
+        });
+    }
+
+    #[test]
+    fn drop_newest_is_not_a_missing_reader() {
+        let channel = Arc::new(EventChannel::new(EventConfig::new(1, OverflowPolicy::DropNewest)));
+        assert!(channel.send(0).is_err());
+        let mut reader = ChannelReader::new(channel.clone());
+        assert_eq!(channel.send(1).ok(), Some(1));
+        assert_eq!(channel.send(2).ok(), Some(0));
+        assert_eq!(reader.try_recv().ok(), Some(1));
+        assert!(matches!(reader.try_recv(), Err(TryRecvError::Empty)));
+        assert_eq!(channel.stats().dropped, 1);
+    }
+}
@@ -0,0 +1,53 @@
+pub mod channel;
+
+/// Events collected during one frame. The app clears the `ApplicationEvent`
+/// queue right before pumping winit, so `Update` systems see exactly the
+/// events of the current frame.
+pub struct Events<T> {
+    events: Vec<T>,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Self {
+        Events { events: Vec::new() }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.events.push(event);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.events.iter()
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.events.drain(..)
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Events<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.events.iter()
+    }
+}
@@ -1,9 +1,18 @@
-use std::time::Duration;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 pub use base_derive::Resource;
+use context::{EventLoopContext, InjectedEvents, SuppressLiveInput};
+use events::{
+    Events,
+    channel::{ChannelReader, EventChannel, EventConfig, EventStats},
+};
+use profiling::{ProfileKind, SystemProfile};
 use runtime::Runtime;
-use storage::{Res, ResMut, Resource};
-use tokio::sync::broadcast::{Receiver as TokioReceiver, Sender};
+use storage::{FromResources, Res, ResMut, Resource};
+use time::{Time, Timers};
 pub use uuid::Uuid;
 pub use winit::*;
 pub use winit::{
@@ -13,9 +22,20 @@ pub use winit::{
     platform::pump_events::EventLoopExtPumpEvents,
     window::WindowId,
 };
+pub mod context;
+pub mod dynamic;
+pub mod events;
+pub mod logging;
+pub mod plugin_group;
+pub mod profiling;
+// serves the app to anything that can reach the socket, opt in only
+#[cfg(feature = "remote")]
+pub mod remote;
+pub mod rollback;
 pub mod runtime;
 pub mod storage;
 pub mod system;
+pub mod time;
 
 pub mod math {
     pub use nalgebra::*;
@@ -29,14 +49,20 @@ pub mod math {
 
 use system::IntoSystem;
 
-use crate::system::scheduler::{Label, StoredSystem};
-use crate::system::{IntoStoredSystem, IntoStoredSystems};
-use crate::system::{IntoWindowEventSystem, commands::CommandList, scheduler::Scheduler};
+use crate::plugin_group::PluginGroup;
+use crate::dynamic::{DynamicPlugin, PluginLoadError, PluginRequest, PluginRequests};
+use crate::system::scheduler::{Label, SystemId};
+use crate::system::{hooks::ResourceHooks, IntoResult, IntoStoredSystems, SystemError, SystemErrorHandler};
+use crate::rollback::RollbackRegistry;
+use crate::system::pipe::{IntoIoSystem, IoSystem};
+use crate::system::undo::UndoStack;
+use crate::system::{IntoWindowEventSystem, commands::{CommandError, CommandErrorHandler, CommandList, CommandQueue}, scheduler::Scheduler};
 
 extern crate self as app_base;
 
 pub struct App {
     plugins: Vec<PluginLifetime>,
+    dynamic_plugins: Vec<DynamicPlugin>,
     scheduler: Scheduler,
 }
 
@@ -74,7 +100,7 @@ impl Plugin for PluginLifetime {
 
 pub struct Quit;
 
-pub struct EventReader<T>(tokio::sync::broadcast::Receiver<T>);
+pub struct EventReader<T: Clone>(ChannelReader<T>);
 
 impl<T: Clone> Clone for EventReader<T> {
     fn clone(&self) -> Self {
@@ -82,12 +108,17 @@ impl<T: Clone> Clone for EventReader<T> {
     }
 }
 
-#[derive(Clone)]
-pub struct EventWriter<T>(tokio::sync::broadcast::Sender<T>);
+pub struct EventWriter<T>(Arc<EventChannel<T>>);
+
+impl<T> Clone for EventWriter<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
 
 impl<T: Clone> EventReader<T> {
-    pub fn new(sender: tokio::sync::broadcast::Sender<T>) -> Self {
-        EventReader(sender.subscribe())
+    pub fn new(channel: Arc<EventChannel<T>>) -> Self {
+        EventReader(ChannelReader::new(channel))
     }
     pub async fn recv(&mut self) -> Result<T, tokio::sync::broadcast::error::RecvError> {
         self.0.recv().await
@@ -95,15 +126,25 @@ impl<T: Clone> EventReader<T> {
     pub fn try_recv(&mut self) -> Result<T, tokio::sync::broadcast::error::TryRecvError> {
         self.0.try_recv()
     }
+    pub fn stats(&self) -> EventStats {
+        self.0.channel().stats()
+    }
+    /// A handle reading from the same cursor, the subscription stays with `self`.
+    pub fn share(&self) -> Self {
+        EventReader(self.0.share())
+    }
 }
 
 impl<T: Clone> EventWriter<T> {
-    pub fn new(sender: tokio::sync::broadcast::Sender<T>) -> Self {
-        EventWriter(sender)
+    pub fn new(channel: Arc<EventChannel<T>>) -> Self {
+        EventWriter(channel)
     }
     pub fn send(&self, event: T) -> Result<usize, tokio::sync::broadcast::error::SendError<T>> {
         self.0.send(event)
     }
+    pub fn stats(&self) -> EventStats {
+        self.0.stats()
+    }
 }
 
 pub trait Plugin {
@@ -112,22 +153,157 @@ pub trait Plugin {
 
 impl App {
     pub fn new() -> Self {
+        let mut scheduler = Scheduler::new();
+        let queue = CommandQueue::new();
+        scheduler.add_resource(queue.sender());
+        scheduler.add_resource(queue);
+        scheduler.add_resource(CommandList::new());
+        scheduler.add_resource(Time::new());
+        scheduler.add_resource(Timers::new());
+        scheduler.add_resource(Events::<ApplicationEvent>::new());
+        // created on demand, often first by a plugin, but shared with the host
+        let resources = scheduler.resources_mut();
+        resources.share::<UndoStack>();
+        resources.share::<PluginRequests>();
+        resources.share::<RollbackRegistry>();
         App {
             plugins: Vec::new(),
-            scheduler: Scheduler::new(),
+            dynamic_plugins: Vec::new(),
+            scheduler,
         }
     }
 
     pub fn add_plugin<P: Plugin + 'static>(&mut self, plugin: P) {
+        self.add_boxed_plugin(std::any::type_name::<P>(), Box::new(plugin));
+    }
+
+    pub fn add_boxed_plugin(&mut self, name: &'static str, plugin: Box<dyn Plugin>) {
+        let previous = self.scheduler.set_current_plugin(Some(name));
         plugin.build(self);
-        let wrapper = PluginLifetime::startup(Box::new(plugin));
+        self.scheduler.set_current_plugin(previous);
+        let wrapper = PluginLifetime::startup(plugin);
         self.plugins.push(wrapper);
     }
 
+    pub fn add_plugins<G: PluginGroup>(&mut self, group: G) {
+        group.build().finish(self);
+    }
+
+    /// Loads a plugin exported with [`export_plugin!`] from a shared library and builds it.
+    pub fn load_plugin<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<&'static str, PluginLoadError> {
+        let plugin = DynamicPlugin::load(path)?;
+        let name = plugin.name();
+        if self.dynamic_plugins.iter().any(|loaded| loaded.name() == name) {
+            return Err(PluginLoadError::AlreadyLoaded(name.to_string()));
+        }
+        self.scheduler.resources_mut().add_library(name);
+        let previous = self.scheduler.set_current_plugin(Some(name));
+        plugin.plugin().build(self);
+        self.scheduler.set_current_plugin(previous);
+        log::info!("loaded plugin {} from {}", name, plugin.path().display());
+        self.dynamic_plugins.push(plugin);
+        Ok(name)
+    }
+
+    /// Removes the plugin's systems, observers, hooks and resources, then closes its library.
+    /// Refused while its async tasks are running or it replaced a resource the host can't take over.
+    pub fn unload_plugin(&mut self, name: &str) -> Result<(), PluginLoadError> {
+        let index = self
+            .dynamic_plugins
+            .iter()
+            .position(|plugin| plugin.name() == name)
+            .ok_or_else(|| PluginLoadError::NotLoaded(name.to_string()))?;
+        let in_use = |reason: String| PluginLoadError::InUse {
+            name: name.to_string(),
+            reason,
+        };
+        let tasks = self
+            .scheduler
+            .get_resource::<Runtime>()
+            .map(|runtime| runtime.running_tasks(name))
+            .unwrap_or(0);
+        if tasks > 0 {
+            return Err(in_use(format!("{} of its tasks are still running", tasks)));
+        }
+        // what it queued runs while its code is still there
+        self.handle_commands();
+        let unmovable = self.scheduler.resources().unmovable(name);
+        if !unmovable.is_empty() {
+            return Err(in_use(format!("it replaced {} which only its own code can drop", unmovable.join(", "))));
+        }
+        self.scheduler.remove_plugin(name);
+        drop(self.dynamic_plugins.remove(index));
+        Ok(())
+    }
+
+    pub fn reload_plugin(&mut self, name: &str) -> Result<&'static str, PluginLoadError> {
+        let path = self
+            .dynamic_plugins
+            .iter()
+            .find(|plugin| plugin.name() == name)
+            .map(|plugin| plugin.path().to_path_buf())
+            .ok_or_else(|| PluginLoadError::NotLoaded(name.to_string()))?;
+        self.unload_plugin(name)?;
+        self.load_plugin(path)
+    }
+
+    fn handle_plugin_requests(&mut self) {
+        let requests = match self.scheduler.get_resource_mut::<PluginRequests>() {
+            Some(mut requests) => std::mem::take(&mut requests.requests),
+            None => return,
+        };
+        for request in requests {
+            let result = match &request {
+                PluginRequest::Load(path) => self.load_plugin(path).map(|_| ()),
+                PluginRequest::Unload(name) => self.unload_plugin(name),
+                PluginRequest::Reload(name) => self.reload_plugin(name).map(|_| ()),
+            };
+            if let Err(e) = result {
+                log::error!("{:?} failed: {}", request, e);
+            }
+        }
+    }
+
     pub fn add_systems<S: IntoStoredSystems<I>, I>(&mut self, label: impl Label, systems: S) {
         self.scheduler.add_systems(label, systems);
     }
 
+    pub fn register_system<S: IntoSystem<I>, I>(&mut self, system: S) -> SystemId
+    where
+        S::System: 'static,
+    {
+        self.scheduler.register_system(system)
+    }
+
+    pub fn add_observer<S: IntoIoSystem<I>, I>(&mut self, system: S)
+    where
+        S::System: 'static,
+        <S::System as IoSystem>::In: Clone + 'static,
+        <S::System as IoSystem>::Out: IntoResult,
+    {
+        self.scheduler.add_observer(system);
+    }
+
+    /// Hooks that run synchronously when the scheduler inserts, replaces or removes `T`.
+    pub fn resource_hooks<T: Resource + 'static>(&mut self) -> &mut ResourceHooks {
+        self.scheduler.resource_hooks::<T>()
+    }
+
+    /// Registers the channel behind `EventReader<T>`/`EventWriter<T>`, or reconfigures it if a system already created it.
+    pub fn add_event<T: Clone + 'static>(&mut self, config: EventConfig) {
+        if let Some(channel) = self.scheduler.get_resource::<ChannelResource<T>>() {
+            channel.channel().set_config(config);
+            return;
+        }
+        self.scheduler.add_resource(ChannelResource::<T>::new(config));
+    }
+
+    pub fn event_stats<T: Clone + 'static>(&self) -> Option<EventStats> {
+        self.scheduler
+            .get_resource::<ChannelResource<T>>()
+            .map(|channel| channel.stats())
+    }
+
     pub fn add_window_event_system<S: IntoWindowEventSystem<I> + 'static, I: 'static>(
         &mut self,
         system: S,
@@ -135,30 +311,99 @@ impl App {
         self.scheduler.add_window_event_system(system);
     }
 
-    pub fn run_event(&mut self, event: ApplicationEvent, event_loop: &ActiveEventLoop) {
+    pub fn run_event(&mut self, event: ApplicationEvent, event_loop: &dyn EventLoopContext) {
         self.scheduler.run_events(event, event_loop);
     }
 
-    fn handle_commands(&mut self) {
-        let commands = if let Some(mut commands) = self.scheduler.get_resource_mut::<CommandList>()
+    /// Runs the events queued in [`InjectedEvents`], the event loop does this once per pump.
+    pub fn run_injected_events(&mut self, event_loop: &dyn EventLoopContext) {
+        while let Some(event) = self
+            .scheduler
+            .get_resource_mut::<InjectedEvents>()
+            .and_then(|mut injected| injected.pop())
         {
-            commands.take()
-        } else {
-            Vec::new()
+            self.run_event(event, event_loop);
+        }
+    }
+
+    pub fn schedule_graph(&self) -> String {
+        system::graph::schedule_graph(&self.scheduler)
+    }
+
+    pub fn export_schedule_graph<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        system::graph::write_schedule_graph(&self.scheduler, path)
+    }
+
+    pub fn enable_profiling(&mut self) {
+        self.add_resource(SystemProfile::new());
+    }
+
+    pub fn enable_chrome_trace<P: AsRef<std::path::Path>>(&mut self, path: P) {
+        self.add_resource(SystemProfile::new().with_chrome_trace(path));
+    }
+
+    pub fn set_command_error_handler<F: FnMut(&CommandError) + 'static>(&mut self, handler: F) {
+        self.add_resource(CommandErrorHandler::new(handler));
+    }
+
+    pub fn set_system_error_handler<F: FnMut(&SystemError) + 'static>(&mut self, handler: F) {
+        self.add_resource(SystemErrorHandler::new(handler));
+    }
+
+    fn handle_commands(&mut self) {
+        let start = Instant::now();
+        self.scheduler.apply_commands();
+        if let Some(mut profile) = self.scheduler.get_resource_mut::<SystemProfile>() {
+            profile.record("commands", ProfileKind::Commands, None, start, start.elapsed());
+        }
+    }
+
+    fn update_time(&mut self) {
+        let Some(delta) = self.scheduler.get_resource_mut::<Time>().map(|mut time| {
+            time.update();
+            time.delta()
+        }) else {
+            return;
+        };
+        let due = match self.scheduler.get_resource_mut::<Timers>() {
+            Some(mut timers) => timers.tick(delta),
+            None => return,
         };
-        commands
-            .into_iter()
-            .for_each(|command| command.execute_boxed(&mut self.scheduler));
+        if let Some(mut list) = self.scheduler.get_resource_mut::<CommandList>() {
+            due.into_iter().for_each(|command| list.push(command));
+        }
+    }
+
+    /// Runs one frame on the events received since the last one, then clears them.
+    /// `run` calls this after every pump, tests can drive frames with a [`context::MockEventLoop`].
+    pub fn update(&mut self) {
+        self.handle_commands();
+        self.update_time();
+        self.scheduler.run();
+        self.handle_commands();
+        self.handle_plugin_requests();
+        if let Some(mut events) = self.scheduler.get_resource_mut::<Events<ApplicationEvent>>() {
+            events.clear();
+        }
     }
 
     pub fn run(&mut self) -> Result<(), winit::error::EventLoopError> {
         dotenvy::dotenv().ok();
-        env_logger::init();
+        // LogPlugin may have set up the logger already
+        let _ = env_logger::try_init();
         let rt = Runtime::new();
         let mut event_loop = EventLoop::new()?;
         log::debug!("Starting app with {} plugins", self.plugins.len());
 
         self.add_resource(rt);
+        if let Ok(path) = std::env::var("SRAF_TRACE") {
+            self.enable_chrome_trace(path);
+        }
+        if let Ok(path) = std::env::var("SRAF_SCHEDULE_GRAPH")
+            && let Err(e) = self.export_schedule_graph(&path)
+        {
+            log::warn!("failed to write schedule graph to {path}: {e}");
+        }
 
         self.scheduler.startup();
         self.handle_commands();
@@ -167,16 +412,19 @@ impl App {
                 Some(Duration::from_millis(16)),
                 &mut AppHandler { app: self },
             );
-            self.handle_commands();
-
-            self.scheduler.run();
-            self.handle_commands();
+            self.update();
 
             if self.should_close() {
                 break;
             }
         }
 
+        if let Some(mut profile) = self.scheduler.get_resource_mut::<SystemProfile>()
+            && let Err(e) = profile.write_trace()
+        {
+            log::warn!("failed to write chrome trace: {e}");
+        }
+
         Ok(())
         // let mut rt = tokio::runtime::Runtime::new().unwrap();
         // rt.block_on(async move {<|cursor|>});
@@ -194,6 +442,40 @@ impl App {
         self.scheduler.add_resource(resource);
     }
 
+    /// Adds `T` built from `Default` or [`FromResources`], keeping an existing value.
+    pub fn init_resource<T: Resource + FromResources + 'static>(&mut self) {
+        self.scheduler.init_resource::<T>();
+    }
+
+    /// Includes `T` in rollback snapshots.
+    pub fn register_rollback<T: Resource + Clone + 'static>(&mut self) {
+        self.scheduler.register_rollback::<T>();
+    }
+
+    /// Lets the remote protocol read and write `T`, see [`remote::RemotePlugin`].
+    #[cfg(feature = "remote")]
+    pub fn register_remote<T: Resource + remote::Reflect>(&mut self) {
+        self.scheduler.init_resource::<remote::RemoteRegistry>();
+        if let Some(mut registry) = self.scheduler.get_resource_mut::<remote::RemoteRegistry>() {
+            registry.register::<T>();
+        }
+    }
+
+    #[cfg(feature = "remote")]
+    pub fn register_remote_command<F>(&mut self, name: impl Into<String>, build: F)
+    where
+        F: Fn(&remote::Value) -> Result<Box<dyn system::commands::Command>, String> + 'static,
+    {
+        self.scheduler.init_resource::<remote::RemoteRegistry>();
+        if let Some(mut registry) = self.scheduler.get_resource_mut::<remote::RemoteRegistry>() {
+            registry.register_command(name, build);
+        }
+    }
+
+    pub fn add_keyed_resource<K: std::hash::Hash + Eq + 'static, T: Resource + 'static>(&mut self, key: K, resource: T) {
+        self.scheduler.add_keyed_resource(key, resource);
+    }
+
     fn should_close(&self) -> bool {
         self.scheduler.get_resource::<Quit>().is_some()
     }
@@ -206,7 +488,7 @@ impl App {
     // }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ApplicationEvent {
     Quit,
     WindowEvent { id: WindowId, event: WindowEvent },
@@ -214,45 +496,23 @@ pub enum ApplicationEvent {
     Suspended,
 }
 
-struct ChannelResource<T: 'static> {
-    sender: Sender<T>,
-    receiver: TokioReceiver<T>,
-}
-
-pub struct Receiver<T: Clone> {
-    inner: TokioReceiver<T>,
-}
-
-impl<T: Clone> From<TokioReceiver<T>> for Receiver<T> {
-    fn from(inner: TokioReceiver<T>) -> Self {
-        Receiver { inner }
-    }
-}
-
-impl<T: Clone> Receiver<T> {
-    pub async fn recv(&mut self) -> Result<T, tokio::sync::broadcast::error::RecvError> {
-        self.inner.recv().await
-    }
-
-    pub fn try_recv(&mut self) -> Result<T, tokio::sync::broadcast::error::TryRecvError> {
-        self.inner.try_recv()
-    }
+pub(crate) struct ChannelResource<T: 'static> {
+    channel: Arc<EventChannel<T>>,
 }
 
 impl<T: Clone> ChannelResource<T> {
-    pub fn new(cap: usize) -> Self {
-        let (sender, receiver) = tokio::sync::broadcast::channel(cap);
-        ChannelResource { sender, receiver }
+    pub fn new(config: EventConfig) -> Self {
+        ChannelResource {
+            channel: Arc::new(EventChannel::new(config)),
+        }
     }
 
-    pub fn send(&self, value: T) -> Result<usize, tokio::sync::broadcast::error::SendError<T>> {
-        self.sender.send(value)
+    pub fn channel(&self) -> &Arc<EventChannel<T>> {
+        &self.channel
     }
 
-    pub fn subscribe(&mut self) -> Receiver<T> {
-        Receiver {
-            inner: self.receiver.resubscribe(),
-        }
+    pub fn stats(&self) -> EventStats {
+        self.channel.stats()
     }
 }
 
@@ -265,12 +525,24 @@ impl ApplicationHandler for AppHandler<'_> {
         self.app.run_event(ApplicationEvent::Resumed, event_loop);
     }
 
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        self.app.run_injected_events(event_loop);
+    }
+
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
         window_id: WindowId,
         event: WindowEvent,
     ) {
+        let suppressed = self
+            .app
+            .scheduler
+            .get_resource::<SuppressLiveInput>()
+            .is_some_and(|suppress| (suppress.filter)(&event));
+        if suppressed {
+            return;
+        }
         self.app.run_event(
             ApplicationEvent::WindowEvent {
                 id: window_id,
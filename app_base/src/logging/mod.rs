@@ -0,0 +1,38 @@
+use log::debug;
+
+use crate::{App, Plugin};
+
+/// Sets up `env_logger`. `RUST_LOG` (also read from `.env`) wins over the default filter.
+pub struct LogPlugin {
+    pub filter: String,
+}
+
+impl Default for LogPlugin {
+    fn default() -> Self {
+        LogPlugin {
+            filter: "info".to_string(),
+        }
+    }
+}
+
+impl LogPlugin {
+    pub fn new(filter: impl Into<String>) -> Self {
+        LogPlugin {
+            filter: filter.into(),
+        }
+    }
+}
+
+impl Plugin for LogPlugin {
+    fn build(&self, _app: &mut App) {
+        dotenvy::dotenv().ok();
+        let mut builder = env_logger::Builder::new();
+        builder.parse_filters(&self.filter);
+        if let Ok(filter) = std::env::var("RUST_LOG") {
+            builder.parse_filters(&filter);
+        }
+        if builder.try_init().is_err() {
+            debug!("a logger is already set, LogPlugin keeps it");
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    any::{Any, TypeId},
+    fmt::Display,
+};
+
+use crate::{App, Plugin};
+
+/// A set of plugins added together with [`App::add_plugins`]. Members are
+/// identified by their type, so each plugin type appears at most once.
+pub trait PluginGroup: Sized {
+    fn build(self) -> PluginGroupBuilder;
+
+    /// Replaces the member of type `T`, for example to configure it.
+    fn set<T: Plugin + 'static>(self, plugin: T) -> Result<PluginGroupBuilder, PluginGroupError> {
+        self.build().set(plugin)
+    }
+
+    fn disable<T: Plugin + 'static>(self) -> Result<PluginGroupBuilder, PluginGroupError> {
+        self.build().disable::<T>()
+    }
+
+    fn add_before<Target: Plugin + 'static, T: Plugin + 'static>(self, plugin: T) -> Result<PluginGroupBuilder, PluginGroupError> {
+        self.build().add_before::<Target, T>(plugin)
+    }
+
+    fn add_after<Target: Plugin + 'static, T: Plugin + 'static>(self, plugin: T) -> Result<PluginGroupBuilder, PluginGroupError> {
+        self.build().add_after::<Target, T>(plugin)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PluginGroupError {
+    /// The named plugin is not a member of the group.
+    NotInGroup(&'static str),
+}
+
+impl Display for PluginGroupError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PluginGroupError::NotInGroup(name) => write!(f, "{} is not part of this plugin group", name),
+        }
+    }
+}
+
+impl std::error::Error for PluginGroupError {}
+
+struct GroupEntry {
+    id: TypeId,
+    name: &'static str,
+    plugin: Box<dyn Plugin>,
+    enabled: bool,
+}
+
+impl GroupEntry {
+    fn new<T: Plugin + 'static>(plugin: T) -> Self {
+        GroupEntry {
+            id: TypeId::of::<T>(),
+            name: std::any::type_name::<T>(),
+            plugin: Box::new(plugin),
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PluginGroupBuilder {
+    entries: Vec<GroupEntry>,
+}
+
+impl PluginGroupBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn index_of<T: Any>(&self) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == TypeId::of::<T>())
+    }
+
+    fn member<T: Any>(&self) -> Result<usize, PluginGroupError> {
+        self.index_of::<T>()
+            .ok_or(PluginGroupError::NotInGroup(std::any::type_name::<T>()))
+    }
+
+    fn insert<T: Plugin + 'static>(&mut self, index: usize, plugin: T) {
+        if let Some(existing) = self.index_of::<T>() {
+            self.entries.remove(existing);
+        }
+        let index = index.min(self.entries.len());
+        self.entries.insert(index, GroupEntry::new(plugin));
+    }
+
+    /// Appends `plugin`, or moves it to the end if the group already has one of its type.
+    pub fn add_plugin<T: Plugin + 'static>(mut self, plugin: T) -> Self {
+        self.insert(self.entries.len(), plugin);
+        self
+    }
+
+    pub fn set<T: Plugin + 'static>(mut self, plugin: T) -> Result<Self, PluginGroupError> {
+        let index = self.member::<T>()?;
+        self.entries[index] = GroupEntry::new(plugin);
+        Ok(self)
+    }
+
+    pub fn disable<T: Plugin + 'static>(mut self) -> Result<Self, PluginGroupError> {
+        let index = self.member::<T>()?;
+        self.entries[index].enabled = false;
+        Ok(self)
+    }
+
+    pub fn enable<T: Plugin + 'static>(mut self) -> Result<Self, PluginGroupError> {
+        let index = self.member::<T>()?;
+        self.entries[index].enabled = true;
+        Ok(self)
+    }
+
+    pub fn add_before<Target: Plugin + 'static, T: Plugin + 'static>(mut self, plugin: T) -> Result<Self, PluginGroupError> {
+        self.insert_next_to::<Target, T>(plugin, 0)?;
+        Ok(self)
+    }
+
+    pub fn add_after<Target: Plugin + 'static, T: Plugin + 'static>(mut self, plugin: T) -> Result<Self, PluginGroupError> {
+        self.insert_next_to::<Target, T>(plugin, 1)?;
+        Ok(self)
+    }
+
+    // the group is left as it was if `Target` is missing
+    fn insert_next_to<Target: Plugin + 'static, T: Plugin + 'static>(&mut self, plugin: T, offset: usize) -> Result<(), PluginGroupError> {
+        let mut index = self.member::<Target>()? + offset;
+        if let Some(existing) = self.index_of::<T>() {
+            self.entries.remove(existing);
+            if existing < index {
+                index -= 1;
+            }
+        }
+        self.insert(index, plugin);
+        Ok(())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = (&'static str, bool)> {
+        self.entries.iter().map(|entry| (entry.name, entry.enabled))
+    }
+
+    pub fn finish(self, app: &mut App) {
+        for entry in self.entries.into_iter().filter(|entry| entry.enabled) {
+            app.add_boxed_plugin(entry.name, entry.plugin);
+        }
+    }
+}
+
+impl PluginGroup for PluginGroupBuilder {
+    fn build(self) -> PluginGroupBuilder {
+        self
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Write as _,
+    fs::File,
+    io::{BufWriter, Write as _},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use log::warn;
+
+use crate::system::scheduler::SystemId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProfileKind {
+    System,
+    Label,
+    Commands,
+}
+
+impl ProfileKind {
+    fn category(&self) -> &'static str {
+        match self {
+            ProfileKind::System => "system",
+            ProfileKind::Label => "label",
+            ProfileKind::Commands => "commands",
+        }
+    }
+}
+
+/// Systems are told apart by id, two closures can share a type name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProfileKey {
+    System(SystemId),
+    Named(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ProfileStats {
+    pub name: String,
+    pub kind: ProfileKind,
+    pub plugin: Option<&'static str>,
+    pub count: u64,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    samples: VecDeque<Duration>,
+}
+
+impl ProfileStats {
+    fn new(name: &str, kind: ProfileKind, plugin: Option<&'static str>) -> Self {
+        ProfileStats {
+            name: name.to_string(),
+            kind,
+            plugin,
+            count: 0,
+            total: Duration::ZERO,
+            min: Duration::MAX,
+            max: Duration::ZERO,
+            samples: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, duration: Duration, window: usize) {
+        self.count += 1;
+        self.total += duration;
+        self.min = self.min.min(duration);
+        self.max = self.max.max(duration);
+        if self.samples.len() == window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(duration);
+    }
+
+    pub fn last(&self) -> Duration {
+        self.samples.back().copied().unwrap_or_default()
+    }
+
+    pub fn average(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+}
+
+enum TraceOutput {
+    Closed,
+    Open(BufWriter<File>),
+    Failed,
+}
+
+/// Streams events to `path` in the trace event array format as they are recorded,
+/// so nothing piles up in memory. A trace cut short by a crash still loads.
+pub struct ChromeTrace {
+    path: PathBuf,
+    output: TraceOutput,
+    events: u64,
+}
+
+impl ChromeTrace {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        ChromeTrace {
+            path: path.as_ref().to_path_buf(),
+            output: TraceOutput::Closed,
+            events: 0,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn events(&self) -> u64 {
+        self.events
+    }
+
+    fn writer(&mut self) -> Option<&mut BufWriter<File>> {
+        if let TraceOutput::Closed = self.output {
+            self.output = match File::create(&self.path) {
+                Ok(file) => TraceOutput::Open(BufWriter::new(file)),
+                Err(e) => {
+                    warn!("failed to create chrome trace {}: {}", self.path.display(), e);
+                    TraceOutput::Failed
+                }
+            };
+        }
+        match &mut self.output {
+            TraceOutput::Open(writer) => Some(writer),
+            _ => None,
+        }
+    }
+
+    fn push(&mut self, name: &str, kind: ProfileKind, plugin: Option<&'static str>, start: Duration, duration: Duration) {
+        let separator = if self.events == 0 { '[' } else { ',' };
+        let Some(writer) = self.writer() else {
+            return;
+        };
+        let written = writeln!(
+            writer,
+            "{}{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":1,\"tid\":1,\"args\":{{\"plugin\":\"{}\"}}}}",
+            separator,
+            escape(name),
+            kind.category(),
+            start.as_micros(),
+            duration.as_micros(),
+            escape(plugin.unwrap_or("app")),
+        );
+        match written {
+            Ok(()) => self.events += 1,
+            Err(e) => {
+                warn!("failed to write chrome trace {}: {}", self.path.display(), e);
+                self.output = TraceOutput::Failed;
+            }
+        }
+    }
+
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.output {
+            TraceOutput::Open(writer) => writer.flush(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Closes the event array, later events start a new file.
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        if self.events == 0 {
+            return Ok(());
+        }
+        let result = match &mut self.output {
+            TraceOutput::Open(writer) => writer.write_all(b"]\n").and_then(|_| writer.flush()),
+            _ => Ok(()),
+        };
+        self.output = TraceOutput::Closed;
+        self.events = 0;
+        result
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub struct SystemProfile {
+    stats: HashMap<ProfileKey, ProfileStats>,
+    window: usize,
+    origin: Instant,
+    trace: Option<ChromeTrace>,
+}
+
+impl Default for SystemProfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SystemProfile {
+    pub fn new() -> Self {
+        SystemProfile {
+            stats: HashMap::new(),
+            window: 120,
+            origin: Instant::now(),
+            trace: None,
+        }
+    }
+
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    pub fn with_chrome_trace<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.trace = Some(ChromeTrace::new(path));
+        self
+    }
+
+    pub fn record(
+        &mut self,
+        name: &str,
+        kind: ProfileKind,
+        plugin: Option<&'static str>,
+        start: Instant,
+        duration: Duration,
+    ) {
+        self.record_key(ProfileKey::Named(name.to_string()), name, kind, plugin, start, duration);
+    }
+
+    pub fn record_system(
+        &mut self,
+        id: SystemId,
+        name: &str,
+        plugin: Option<&'static str>,
+        start: Instant,
+        duration: Duration,
+    ) {
+        self.record_key(ProfileKey::System(id), name, ProfileKind::System, plugin, start, duration);
+    }
+
+    fn record_key(
+        &mut self,
+        key: ProfileKey,
+        name: &str,
+        kind: ProfileKind,
+        plugin: Option<&'static str>,
+        start: Instant,
+        duration: Duration,
+    ) {
+        let window = self.window;
+        self.stats
+            .entry(key)
+            .or_insert_with(|| ProfileStats::new(name, kind, plugin))
+            .push(duration, window);
+        if let Some(trace) = &mut self.trace {
+            trace.push(name, kind, plugin, start.saturating_duration_since(self.origin), duration);
+        }
+    }
+
+    /// The first entry called `name`, use [`SystemProfile::get_system`] to tell systems apart.
+    pub fn get(&self, name: &str) -> Option<&ProfileStats> {
+        self.stats.values().find(|stats| stats.name == name)
+    }
+
+    pub fn get_system(&self, id: SystemId) -> Option<&ProfileStats> {
+        self.stats.get(&ProfileKey::System(id))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ProfileStats)> {
+        self.stats.values().map(|stats| (stats.name.as_str(), stats))
+    }
+
+    /// Entries of the given kind, slowest rolling average first.
+    pub fn slowest(&self, kind: ProfileKind) -> Vec<(&str, &ProfileStats)> {
+        let mut entries: Vec<_> = self.iter().filter(|(_, s)| s.kind == kind).collect();
+        entries.sort_by_key(|(_, s)| std::cmp::Reverse(s.average()));
+        entries
+    }
+
+    pub fn trace(&self) -> Option<&ChromeTrace> {
+        self.trace.as_ref()
+    }
+
+    pub fn write_trace(&mut self) -> std::io::Result<()> {
+        match &mut self.trace {
+            Some(trace) => trace.finish(),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,684 @@
+use std::{
+    any::TypeId,
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+
+use log::{error, info, warn};
+
+use crate::{
+    App, Plugin, Quit,
+    storage::{Resource, Resources},
+    system::{
+        commands::{Command, CommandList, InsertResource, QueuedCommand},
+        scheduler::{PreUpdate, Scheduler},
+    },
+    time::Time,
+};
+
+pub mod json;
+
+pub use json::Value;
+
+/// Conversion of a resource to and from JSON for the remote protocol.
+pub trait Reflect: Sized + 'static {
+    fn to_json(&self) -> Value;
+
+    fn from_json(_value: &Value) -> Result<Self, String> {
+        Err("read only".to_string())
+    }
+}
+
+macro_rules! impl_reflect_float {
+    ($($ty:ty),*) => {
+        $(impl Reflect for $ty {
+            fn to_json(&self) -> Value {
+                Value::from(*self)
+            }
+
+            fn from_json(value: &Value) -> Result<Self, String> {
+                let n = value.as_f64().ok_or_else(|| "expected a number".to_string())?;
+                let converted = n as $ty;
+                if converted.is_infinite() {
+                    return Err(format!("{} is out of range for {}", n, stringify!($ty)));
+                }
+                Ok(converted)
+            }
+        })*
+    };
+}
+
+// `as` would truncate fractions and saturate out of range values
+macro_rules! impl_reflect_integer {
+    ($($ty:ty),*) => {
+        $(impl Reflect for $ty {
+            fn to_json(&self) -> Value {
+                Value::from(*self)
+            }
+
+            fn from_json(value: &Value) -> Result<Self, String> {
+                let n = value.as_f64().ok_or_else(|| "expected a number".to_string())?;
+                if n.fract() != 0.0 {
+                    return Err(format!("expected an integer, got {}", n));
+                }
+                // MAX + 1 is a power of two and exact as f64, MAX itself may round up to it
+                if n < <$ty>::MIN as f64 || n >= <$ty>::MAX as f64 + 1.0 {
+                    return Err(format!("{} is out of range for {}", n, stringify!($ty)));
+                }
+                Ok(n as $ty)
+            }
+        })*
+    };
+}
+
+impl_reflect_float!(f32, f64);
+impl_reflect_integer!(i32, i64, u32, u64, usize);
+
+impl Reflect for bool {
+    fn to_json(&self) -> Value {
+        Value::Bool(*self)
+    }
+
+    fn from_json(value: &Value) -> Result<Self, String> {
+        value.as_bool().ok_or_else(|| "expected a bool".to_string())
+    }
+}
+
+impl Reflect for String {
+    fn to_json(&self) -> Value {
+        Value::String(self.clone())
+    }
+
+    fn from_json(value: &Value) -> Result<Self, String> {
+        value.as_str().map(str::to_string).ok_or_else(|| "expected a string".to_string())
+    }
+}
+
+impl Reflect for Time {
+    fn to_json(&self) -> Value {
+        Value::object([
+            ("delta", self.delta_secs().into()),
+            ("elapsed", self.elapsed().as_secs_f64().into()),
+            ("frame", self.frame().into()),
+        ])
+    }
+}
+
+type ReadFn = fn(&Resources) -> Option<Value>;
+type WriteFn = fn(&mut Scheduler, &Value) -> Result<(), String>;
+pub type RemoteCommandFn = Box<dyn Fn(&Value) -> Result<Box<dyn Command>, String>>;
+
+fn read_resource<T: Resource + Reflect>(resources: &Resources) -> Option<Value> {
+    resources.get::<T>().map(|resource| resource.to_json())
+}
+
+// goes through the scheduler so hooks and lifecycle events fire as usual
+fn write_resource<T: Resource + Reflect>(scheduler: &mut Scheduler, value: &Value) -> Result<(), String> {
+    let resource = T::from_json(value)?;
+    scheduler.add_resource(resource);
+    Ok(())
+}
+
+struct ReflectedResource {
+    name: &'static str,
+    id: TypeId,
+    read: ReadFn,
+    write: WriteFn,
+}
+
+/// Resources and commands reachable through the remote protocol.
+#[derive(Default)]
+pub struct RemoteRegistry {
+    resources: Vec<ReflectedResource>,
+    commands: Vec<(String, RemoteCommandFn)>,
+}
+
+impl RemoteRegistry {
+    pub fn register<T: Resource + Reflect>(&mut self) {
+        if self.resources.iter().any(|entry| entry.id == T::id()) {
+            return;
+        }
+        self.resources.push(ReflectedResource {
+            name: std::any::type_name::<T>(),
+            id: T::id(),
+            read: read_resource::<T>,
+            write: write_resource::<T>,
+        });
+    }
+
+    /// `build` turns the request params into the command to queue.
+    pub fn register_command<F>(&mut self, name: impl Into<String>, build: F)
+    where
+        F: Fn(&Value) -> Result<Box<dyn Command>, String> + 'static,
+    {
+        let name = name.into();
+        self.commands.retain(|(existing, _)| *existing != name);
+        self.commands.push((name, Box::new(build)));
+    }
+
+    // accepts the full type name or its last path segment
+    fn find(&self, name: &str) -> Result<&ReflectedResource, String> {
+        let mut matches = self.resources.iter().filter(|entry| {
+            entry.name == name || entry.name.rsplit("::").next() == Some(name)
+        });
+        match (matches.next(), matches.next()) {
+            (Some(entry), None) => Ok(entry),
+            (Some(_), Some(_)) => Err(format!("{} is ambiguous, use the full type name", name)),
+            (None, _) => Err(format!("{} is not a reflected resource", name)),
+        }
+    }
+}
+
+pub struct RemoteRequest {
+    pub request: Value,
+    respond: Sender<Value>,
+}
+
+pub struct RemoteServer {
+    receiver: Receiver<RemoteRequest>,
+    address: String,
+}
+
+impl RemoteServer {
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+const PARSE_ERROR: i32 = -32700;
+const METHOD_NOT_FOUND: i32 = -32601;
+const INVALID_PARAMS: i32 = -32602;
+const APP_ERROR: i32 = -32000;
+
+type RpcResult = Result<Value, (i32, String)>;
+
+fn response(id: Value, result: RpcResult) -> Value {
+    match result {
+        Ok(result) => Value::object([("jsonrpc", "2.0".into()), ("id", id), ("result", result)]),
+        Err((code, message)) => Value::object([
+            ("jsonrpc", "2.0".into()),
+            ("id", id),
+            (
+                "error",
+                Value::object([("code", code.into()), ("message", message.into())]),
+            ),
+        ]),
+    }
+}
+
+fn param<'a>(params: &'a Value, key: &str) -> Result<&'a Value, (i32, String)> {
+    params
+        .get(key)
+        .ok_or_else(|| (INVALID_PARAMS, format!("missing param {}", key)))
+}
+
+fn param_str<'a>(params: &'a Value, key: &str) -> Result<&'a str, (i32, String)> {
+    param(params, key)?
+        .as_str()
+        .ok_or_else(|| (INVALID_PARAMS, format!("param {} must be a string", key)))
+}
+
+fn list_resources(registry: &RemoteRegistry, scheduler: &Scheduler) -> Value {
+    let resources = scheduler.resources();
+    let mut names: Vec<_> = resources
+        .ids()
+        .map(|id| {
+            let reflected = registry.resources.iter().any(|entry| entry.id == id);
+            (resources.type_name(id).unwrap_or("<unknown>"), reflected)
+        })
+        .collect();
+    names.sort();
+    Value::Array(
+        names
+            .into_iter()
+            .map(|(name, reflected)| Value::object([("name", name.into()), ("reflected", reflected.into())]))
+            .collect(),
+    )
+}
+
+fn list_schedules(scheduler: &Scheduler) -> Value {
+    let mut labels: Vec<_> = scheduler.labels().collect();
+    labels.sort_by_key(|(order, _)| *order);
+    let system = |name: &str, plugin: Option<&str>| {
+        Value::object([("name", name.into()), ("plugin", plugin.into())])
+    };
+    let schedules = labels
+        .into_iter()
+        .map(|(order, label)| {
+            let systems = scheduler
+                .systems(order)
+                .iter()
+                .map(|s| system(s.system.name(), s.plugin))
+                .collect();
+            Value::object([
+                ("label", label.into()),
+                ("order", order.into()),
+                ("on_demand", scheduler.is_on_demand(order).into()),
+                ("systems", Value::Array(systems)),
+            ])
+        })
+        .collect();
+    let registered = scheduler
+        .registered_systems()
+        .map(|(_, s)| system(s.system.name(), s.plugin))
+        .collect();
+    Value::object([
+        ("schedules", Value::Array(schedules)),
+        ("registered", Value::Array(registered)),
+    ])
+}
+
+fn handle(registry: &RemoteRegistry, scheduler: &mut Scheduler, method: &str, params: &Value) -> RpcResult {
+    match method {
+        "resources.list" => Ok(list_resources(registry, scheduler)),
+        "resources.get" => {
+            let entry = registry.find(param_str(params, "name")?).map_err(|e| (INVALID_PARAMS, e))?;
+            (entry.read)(scheduler.resources())
+                .ok_or_else(|| (APP_ERROR, format!("{} is not present", entry.name)))
+        }
+        "resources.set" => {
+            let entry = registry.find(param_str(params, "name")?).map_err(|e| (INVALID_PARAMS, e))?;
+            (entry.write)(scheduler, param(params, "value")?)
+                .map(|_| Value::Null)
+                .map_err(|e| (INVALID_PARAMS, format!("cannot set {}: {}", entry.name, e)))
+        }
+        "schedules.list" => Ok(list_schedules(scheduler)),
+        "commands.list" => Ok(Value::Array(
+            registry.commands.iter().map(|(name, _)| name.as_str().into()).collect(),
+        )),
+        "commands.run" => {
+            let name = param_str(params, "name")?;
+            let (_, build) = registry
+                .commands
+                .iter()
+                .find(|(command, _)| command == name)
+                .ok_or_else(|| (INVALID_PARAMS, format!("unknown command {}", name)))?;
+            let command = build(params.get("params").unwrap_or(&Value::Null)).map_err(|e| (INVALID_PARAMS, e))?;
+            let mut list = scheduler
+                .get_resource_mut::<CommandList>()
+                .ok_or_else(|| (APP_ERROR, "no CommandList".to_string()))?;
+            list.push(QueuedCommand {
+                command,
+                source: Some("remote"),
+                plugin: None,
+            });
+            Ok(Value::Null)
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+    }
+}
+
+/// Answers the requests that arrived since the last frame.
+pub fn process_remote_requests(scheduler: &mut Scheduler) {
+    let requests: Vec<_> = match scheduler.get_resource::<RemoteServer>() {
+        Some(server) => server.receiver.try_iter().collect(),
+        None => return,
+    };
+    if requests.is_empty() {
+        return;
+    }
+    scheduler.init_resource::<RemoteRegistry>();
+    scheduler.resource_scope::<RemoteRegistry, _>(|scheduler, registry| {
+        for RemoteRequest { request, respond } in requests {
+            let id = request.get("id").cloned().unwrap_or(Value::Null);
+            let result = match request.get("method").and_then(Value::as_str) {
+                Some(method) => handle(registry, scheduler, method, request.get("params").unwrap_or(&Value::Null)),
+                None => Err((INVALID_PARAMS, "missing method".to_string())),
+            };
+            let _ = respond.send(response(id, result));
+        }
+    });
+}
+
+// a line like "POST / HTTP/1.1", which any browser tab can send to a local port
+fn is_http_request_line(line: &str) -> bool {
+    let mut parts = line.trim_end().split(' ');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(_), Some(version), None) => {
+            !method.is_empty() && method.bytes().all(|b| b.is_ascii_uppercase()) && version.starts_with("HTTP/")
+        }
+        _ => false,
+    }
+}
+
+// one json request per line, the app answers it during its next frame
+fn serve(reader: impl BufRead, mut writer: impl Write, sender: Sender<RemoteRequest>, timeout: Duration) {
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        // the body of a web page's request would otherwise run as a json request
+        if is_http_request_line(&line) {
+            warn!("closing remote connection that sent an http request");
+            break;
+        }
+        let reply = match Value::parse(&line) {
+            Ok(request) => {
+                let id = request.get("id").cloned().unwrap_or(Value::Null);
+                // a channel per request, so a late answer can't be taken for the next one's
+                let (respond, responses) = mpsc::channel();
+                if sender.send(RemoteRequest { request, respond }).is_err() {
+                    break;
+                }
+                match responses.recv_timeout(timeout) {
+                    Ok(reply) => reply,
+                    Err(RecvTimeoutError::Timeout) => response(
+                        id,
+                        Err((APP_ERROR, format!("the app did not answer within {:?}", timeout))),
+                    ),
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+            Err(e) => response(Value::Null, Err((PARSE_ERROR, e))),
+        };
+        if writeln!(writer, "{}", reply).is_err() {
+            break;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RemoteAddress {
+    Tcp(String),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+// only a socket nobody listens on any more is in the way, anything else is left alone
+#[cfg(unix)]
+fn remove_stale_socket(path: &std::path::Path) -> std::io::Result<()> {
+    use std::os::unix::{fs::FileTypeExt, net::UnixStream};
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => {
+            if UnixStream::connect(path).is_ok() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AddrInUse,
+                    format!("{} is in use by another server", path.display()),
+                ));
+            }
+            std::fs::remove_file(path)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn listen(address: &RemoteAddress, sender: Sender<RemoteRequest>, timeout: Duration) -> std::io::Result<String> {
+    match address {
+        RemoteAddress::Tcp(address) => {
+            let listener = TcpListener::bind(address)?;
+            let local = listener.local_addr()?.to_string();
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let sender = sender.clone();
+                    thread::spawn(move || match stream.try_clone() {
+                        Ok(writer) => serve(BufReader::new(stream), writer, sender, timeout),
+                        Err(e) => warn!("remote connection failed: {}", e),
+                    });
+                }
+            });
+            Ok(local)
+        }
+        #[cfg(unix)]
+        RemoteAddress::Unix(path) => {
+            use std::os::unix::net::UnixListener;
+            // a socket file left behind by an earlier run would make bind fail
+            remove_stale_socket(path)?;
+            let listener = UnixListener::bind(path)?;
+            thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let sender = sender.clone();
+                    thread::spawn(move || match stream.try_clone() {
+                        Ok(writer) => serve(BufReader::new(stream), writer, sender, timeout),
+                        Err(e) => warn!("remote connection failed: {}", e),
+                    });
+                }
+            });
+            Ok(path.display().to_string())
+        }
+    }
+}
+
+/// Serves JSON-RPC on a local socket so tools and scripts can inspect and
+/// drive the running app. Behind the `remote` feature, nothing is authenticated.
+/// Connections that open with an HTTP request line are closed, so a web page
+/// can't post requests to the TCP port.
+pub struct RemotePlugin {
+    pub address: RemoteAddress,
+    /// How long a connection waits for the app to answer, a stalled frame
+    /// gets an error reply instead of hanging the client.
+    pub response_timeout: Duration,
+}
+
+const DEFAULT_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl Default for RemotePlugin {
+    fn default() -> Self {
+        Self::tcp("127.0.0.1:15702")
+    }
+}
+
+impl RemotePlugin {
+    pub fn tcp(address: impl Into<String>) -> Self {
+        RemotePlugin {
+            address: RemoteAddress::Tcp(address.into()),
+            response_timeout: DEFAULT_RESPONSE_TIMEOUT,
+        }
+    }
+
+    #[cfg(unix)]
+    pub fn unix(path: impl Into<PathBuf>) -> Self {
+        RemotePlugin {
+            address: RemoteAddress::Unix(path.into()),
+            response_timeout: DEFAULT_RESPONSE_TIMEOUT,
+        }
+    }
+}
+
+impl Plugin for RemotePlugin {
+    fn build(&self, app: &mut App) {
+        let (sender, receiver) = mpsc::channel();
+        let address = match listen(&self.address, sender, self.response_timeout) {
+            Ok(address) => address,
+            Err(e) => {
+                error!("remote plugin could not listen on {:?}: {}", self.address, e);
+                return;
+            }
+        };
+        info!("remote protocol listening on {}", address);
+        app.add_resource(RemoteServer { receiver, address });
+        app.register_remote::<Time>();
+        app.register_remote_command("quit", |_| Ok(Box::new(InsertResource { resource: Quit })));
+        app.add_systems(PreUpdate, process_remote_requests);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn scheduler() -> Scheduler {
+        let mut scheduler = Scheduler::new();
+        scheduler.init_resource::<CommandList>();
+        scheduler.add_resource(Time::new());
+        scheduler.add_resource(3u32);
+        let mut registry = RemoteRegistry::default();
+        registry.register::<Time>();
+        registry.register::<u32>();
+        registry.register_command("quit", |_| Ok(Box::new(InsertResource { resource: Quit })));
+        scheduler.add_resource(registry);
+        scheduler
+    }
+
+    fn call(scheduler: &mut Scheduler, method: &str, params: Value) -> RpcResult {
+        scheduler.resource_scope::<RemoteRegistry, _>(|scheduler, registry| handle(registry, scheduler, method, &params))
+    }
+
+    fn error_code(result: RpcResult) -> i32 {
+        result.unwrap_err().0
+    }
+
+    #[test]
+    fn reads_and_writes_reflected_resources() {
+        let mut scheduler = scheduler();
+        let time = call(&mut scheduler, "resources.get", Value::object([("name", "Time".into())])).unwrap();
+        assert_eq!(time.get("frame"), Some(&Value::Number(0.0)));
+
+        let set = Value::object([("name", "u32".into()), ("value", 7.into())]);
+        assert_eq!(call(&mut scheduler, "resources.set", set), Ok(Value::Null));
+        assert_eq!(*scheduler.get_resource::<u32>().unwrap(), 7);
+
+        let read_only = Value::object([("name", "Time".into()), ("value", Value::Null)]);
+        assert_eq!(error_code(call(&mut scheduler, "resources.set", read_only)), INVALID_PARAMS);
+    }
+
+    #[test]
+    fn rejects_numbers_that_do_not_fit() {
+        assert_eq!(u32::from_json(&Value::Number(7.0)), Ok(7));
+        assert!(u32::from_json(&Value::Number(1.5)).is_err());
+        assert!(u32::from_json(&Value::Number(-1.0)).is_err());
+        assert!(u32::from_json(&Value::Number(4_294_967_296.0)).is_err());
+        assert_eq!(i32::from_json(&Value::Number(-2_147_483_648.0)), Ok(i32::MIN));
+        assert!(i64::from_json(&Value::Number(9_223_372_036_854_775_808.0)).is_err());
+        assert!(u64::from_json(&Value::Number(18_446_744_073_709_551_616.0)).is_err());
+        assert!(f32::from_json(&Value::Number(1e300)).is_err());
+        assert_eq!(f64::from_json(&Value::Number(0.25)), Ok(0.25));
+
+        let mut scheduler = scheduler();
+        let set = Value::object([("name", "u32".into()), ("value", Value::Number(2.5))]);
+        assert_eq!(error_code(call(&mut scheduler, "resources.set", set)), INVALID_PARAMS);
+        assert_eq!(*scheduler.get_resource::<u32>().unwrap(), 3);
+    }
+
+    #[test]
+    fn reports_bad_requests() {
+        let mut scheduler = scheduler();
+        assert_eq!(error_code(call(&mut scheduler, "resources.drop", Value::Null)), METHOD_NOT_FOUND);
+        assert_eq!(error_code(call(&mut scheduler, "resources.get", Value::Null)), INVALID_PARAMS);
+        let unknown = Value::object([("name", "Missing".into())]);
+        assert_eq!(error_code(call(&mut scheduler, "resources.get", unknown)), INVALID_PARAMS);
+        let command = Value::object([("name", "explode".into())]);
+        assert_eq!(error_code(call(&mut scheduler, "commands.run", command)), INVALID_PARAMS);
+    }
+
+    #[test]
+    fn queues_remote_commands() {
+        let mut scheduler = scheduler();
+        let commands = call(&mut scheduler, "commands.list", Value::Null).unwrap();
+        assert_eq!(commands, Value::Array(vec!["quit".into()]));
+        let quit = Value::object([("name", "quit".into())]);
+        assert_eq!(call(&mut scheduler, "commands.run", quit), Ok(Value::Null));
+        scheduler.apply_commands();
+        assert!(scheduler.get_resource::<Quit>().is_some());
+    }
+
+    #[test]
+    fn answers_pending_requests_with_their_id() {
+        let mut scheduler = scheduler();
+        let (sender, receiver) = mpsc::channel();
+        scheduler.add_resource(RemoteServer {
+            receiver,
+            address: String::new(),
+        });
+        let (respond, responses) = mpsc::channel();
+        let request = Value::parse(r#"{"jsonrpc":"2.0","id":4,"method":"resources.get","params":{"name":"u32"}}"#).unwrap();
+        sender.send(RemoteRequest { request, respond }).unwrap();
+        process_remote_requests(&mut scheduler);
+        let reply = responses.try_recv().unwrap();
+        assert_eq!(reply.get("id"), Some(&Value::Number(4.0)));
+        assert_eq!(reply.get("result"), Some(&Value::Number(3.0)));
+    }
+
+    fn replies(output: Vec<u8>) -> Vec<Value> {
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| Value::parse(line).unwrap())
+            .collect()
+    }
+
+    fn error_of(reply: &Value) -> Option<f64> {
+        reply.get("error").and_then(|error| error.get("code")).and_then(Value::as_f64)
+    }
+
+    #[test]
+    fn serve_answers_parse_errors_and_forwards_requests() {
+        let (sender, receiver) = mpsc::channel::<RemoteRequest>();
+        let app = thread::spawn(move || {
+            for RemoteRequest { request, respond } in receiver {
+                let id = request.get("id").cloned().unwrap_or(Value::Null);
+                let _ = respond.send(response(id, Ok("pong".into())));
+            }
+        });
+        let input = "{\"id\":1,\n\n[[[\n{\"id\":2,\"method\":\"ping\"}\n";
+        let mut output = Vec::new();
+        serve(Cursor::new(input), &mut output, sender, Duration::from_secs(5));
+        app.join().unwrap();
+        let replies = replies(output);
+        assert_eq!(replies.len(), 3);
+        assert_eq!(error_of(&replies[0]), Some(PARSE_ERROR as f64));
+        assert_eq!(error_of(&replies[1]), Some(PARSE_ERROR as f64));
+        assert_eq!(replies[2].get("id"), Some(&Value::Number(2.0)));
+        assert_eq!(replies[2].get("result").and_then(Value::as_str), Some("pong"));
+    }
+
+    #[test]
+    fn serve_times_out_when_the_app_does_not_answer() {
+        let (sender, receiver) = mpsc::channel::<RemoteRequest>();
+        let input = "{\"id\":1,\"method\":\"ping\"}\n{\"id\":2,\"method\":\"ping\"}\n";
+        let mut output = Vec::new();
+        serve(Cursor::new(input), &mut output, sender, Duration::from_millis(10));
+        // answered late, after the connection gave up on the request
+        for RemoteRequest { respond, .. } in receiver.try_iter() {
+            assert!(respond.send(Value::Null).is_err());
+        }
+        let replies = replies(output);
+        assert_eq!(replies.len(), 2);
+        for (id, reply) in replies.iter().enumerate() {
+            assert_eq!(reply.get("id"), Some(&Value::Number(id as f64 + 1.0)));
+            assert_eq!(error_of(reply), Some(APP_ERROR as f64));
+        }
+    }
+
+    #[test]
+    fn serve_drops_http_requests() {
+        let (sender, receiver) = mpsc::channel::<RemoteRequest>();
+        let input = "POST / HTTP/1.1\r\nHost: 127.0.0.1:15702\r\nContent-Type: text/plain\r\n\r\n\
+                     {\"id\":1,\"method\":\"commands.run\",\"params\":{\"name\":\"quit\"}}\n";
+        let mut output = Vec::new();
+        serve(Cursor::new(input), &mut output, sender, Duration::from_secs(5));
+        assert!(receiver.try_recv().is_err());
+        assert!(output.is_empty());
+        assert!(is_http_request_line("GET /favicon.ico HTTP/1.1\r"));
+        assert!(!is_http_request_line(r#"{"id":1,"method":"GET / HTTP/1.1"}"#));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_removes_stale_sockets() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = std::env::temp_dir().join(format!("remote-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("not-a-socket");
+        std::fs::write(&file, "keep").unwrap();
+        remove_stale_socket(&file).unwrap();
+        assert!(file.exists());
+
+        let socket = dir.join("socket");
+        let listener = UnixListener::bind(&socket).unwrap();
+        assert_eq!(remove_stale_socket(&socket).unwrap_err().kind(), std::io::ErrorKind::AddrInUse);
+        drop(listener);
+        remove_stale_socket(&socket).unwrap();
+        assert!(!socket.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{HashMap, VecDeque},
+};
+
+use log::debug;
+
+use crate::{
+    App, Plugin,
+    storage::{Resource, Resources},
+    system::scheduler::{PostUpdate, Scheduler},
+};
+
+type CloneFn = fn(&dyn Any) -> Box<dyn Any>;
+
+fn clone_resource<T: Clone + 'static>(value: &dyn Any) -> Box<dyn Any> {
+    Box::new(value.downcast_ref::<T>().unwrap().clone())
+}
+
+struct RollbackEntry {
+    name: &'static str,
+    clone: CloneFn,
+    // the plugin whose code `clone` lives in
+    plugin: Option<&'static str>,
+}
+
+/// The resource types that take part in snapshots. Everything else is left
+/// alone by both snapshot and restore, so non `Clone` resources such as GPU
+/// handles simply keep their current value when the world is rewound.
+#[derive(Default)]
+pub struct RollbackRegistry {
+    entries: HashMap<TypeId, RollbackEntry>,
+}
+
+impl RollbackRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<T: Resource + Clone + 'static>(&mut self) {
+        self.register_for::<T>(None);
+    }
+
+    pub(crate) fn register_for<T: Resource + Clone + 'static>(&mut self, plugin: Option<&'static str>) {
+        self.entries.insert(
+            T::id(),
+            RollbackEntry {
+                name: std::any::type_name::<T>(),
+                clone: clone_resource::<T>,
+                plugin,
+            },
+        );
+    }
+
+    /// Forgets the types `plugin` registered and returns them.
+    pub(crate) fn remove_plugin(&mut self, plugin: &str) -> Vec<TypeId> {
+        let removed: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.plugin == Some(plugin))
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &removed {
+            self.entries.remove(id);
+        }
+        removed
+    }
+
+    pub fn is_registered<T: Resource + 'static>(&self) -> bool {
+        self.entries.contains_key(&T::id())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn snapshot(&self, frame: u64, resources: &Resources) -> Snapshot {
+        let entries = self
+            .entries
+            .iter()
+            .map(|(id, entry)| SnapshotEntry {
+                id: *id,
+                value: resources
+                    .get_entry(*id)
+                    .map(|value| (entry.clone)(value.as_ref())),
+            })
+            .collect();
+        Snapshot { frame, entries }
+    }
+
+    /// The values to write back, the snapshot itself stays valid so it can be
+    /// restored again. `None` marks types missing when the snapshot was taken.
+    pub fn restored(&self, snapshot: &Snapshot) -> Vec<(TypeId, Option<Box<dyn Any>>)> {
+        snapshot
+            .entries
+            .iter()
+            .filter_map(|entry| {
+                let registered = self.entries.get(&entry.id)?;
+                let value = entry.value.as_ref().map(|value| (registered.clone)(value.as_ref()));
+                if value.is_none() {
+                    debug!("{} was absent at frame {}, removing it", registered.name, snapshot.frame);
+                }
+                Some((entry.id, value))
+            })
+            .collect()
+    }
+}
+
+struct SnapshotEntry {
+    id: TypeId,
+    value: Option<Box<dyn Any>>,
+}
+
+pub struct Snapshot {
+    frame: u64,
+    entries: Vec<SnapshotEntry>,
+}
+
+impl Snapshot {
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Ring of the most recent snapshots, oldest first.
+pub struct RollbackBuffer {
+    snapshots: VecDeque<Snapshot>,
+    capacity: usize,
+}
+
+impl RollbackBuffer {
+    pub fn new(capacity: usize) -> Self {
+        RollbackBuffer {
+            snapshots: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    pub fn get(&self, frame: u64) -> Option<&Snapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.frame == frame)
+    }
+
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.snapshots.back()
+    }
+
+    pub fn oldest(&self) -> Option<&Snapshot> {
+        self.snapshots.front()
+    }
+
+    /// Drops every snapshot newer than `frame`, they get recorded again while re-simulating.
+    pub fn truncate_after(&mut self, frame: u64) {
+        while self.snapshots.back().is_some_and(|snapshot| snapshot.frame > frame) {
+            self.snapshots.pop_back();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.snapshots.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    // the values were cloned by code that is about to go away
+    pub(crate) fn forget(&mut self, ids: &[TypeId]) {
+        for snapshot in &mut self.snapshots {
+            snapshot.entries.retain(|entry| !ids.contains(&entry.id));
+        }
+    }
+}
+
+fn record_snapshot(scheduler: &mut Scheduler) {
+    let snapshot = scheduler.snapshot();
+    if let Some(mut buffer) = scheduler.get_resource_mut::<RollbackBuffer>() {
+        buffer.push(snapshot);
+    }
+}
+
+/// Keeps a snapshot of every registered resource for each of the last `capacity` frames.
+pub struct RollbackPlugin {
+    capacity: usize,
+}
+
+impl RollbackPlugin {
+    pub fn new(capacity: usize) -> Self {
+        RollbackPlugin { capacity }
+    }
+}
+
+impl Plugin for RollbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.scheduler.init_shared_resource::<RollbackRegistry>();
+        app.add_resource(RollbackBuffer::new(self.capacity));
+        app.add_systems(PostUpdate, record_snapshot);
+    }
+}
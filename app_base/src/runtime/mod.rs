@@ -1,9 +1,33 @@
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 
 
 #[derive(Clone)]
-pub struct Runtime(Arc<tokio::runtime::Runtime>);
+pub struct Runtime {
+    runtime: Arc<tokio::runtime::Runtime>,
+    // tasks still running per plugin, its library has to stay loaded until they are done
+    tasks: Arc<Mutex<HashMap<&'static str, usize>>>,
+}
+
+struct TaskGuard {
+    plugin: &'static str,
+    tasks: Arc<Mutex<HashMap<&'static str, usize>>>,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        let mut tasks = self.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(count) = tasks.get_mut(self.plugin) {
+            *count -= 1;
+            if *count == 0 {
+                tasks.remove(self.plugin);
+            }
+        }
+    }
+}
 
 impl Runtime {
     pub fn new() -> Self {
@@ -11,7 +35,10 @@ impl Runtime {
             .enable_all()
             .build()
             .unwrap();
-        Runtime(Arc::new(rt))
+        Runtime {
+            runtime: Arc::new(rt),
+            tasks: Arc::new(Mutex::new(HashMap::new())),
+        }
     }
 
     pub fn spawn<F>(&self, future: F) -> tokio::task::JoinHandle<F::Output>
@@ -19,13 +46,43 @@ impl Runtime {
         F: std::future::Future + Send + 'static,
         F::Output: Send + 'static,
     {
-        self.0.spawn(future)
+        self.runtime.spawn(future)
+    }
+
+    /// Spawns a task counted against `plugin` until it finishes or is dropped.
+    pub fn spawn_for<F>(&self, plugin: Option<&'static str>, future: F) -> tokio::task::JoinHandle<F::Output>
+    where
+        F: std::future::Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let Some(plugin) = plugin else {
+            return self.spawn(future);
+        };
+        *self.tasks.lock().unwrap_or_else(|e| e.into_inner()).entry(plugin).or_default() += 1;
+        let guard = TaskGuard {
+            plugin,
+            tasks: self.tasks.clone(),
+        };
+        self.runtime.spawn(async move {
+            let output = future.await;
+            drop(guard);
+            output
+        })
+    }
+
+    pub fn running_tasks(&self, plugin: &str) -> usize {
+        self.tasks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(plugin)
+            .copied()
+            .unwrap_or(0)
     }
 
     pub fn block_on<F>(&self, future: F) -> F::Output
     where
         F: std::future::Future,
     {
-        self.0.block_on(future)
+        self.runtime.block_on(future)
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,4 @@
-use std::{any::{Any, TypeId}, cell::{Ref, RefCell, RefMut}, collections::HashMap, ops::{Deref, DerefMut}};
+use std::{any::{Any, TypeId}, cell::{Ref, RefCell, RefMut}, collections::{HashMap, HashSet}, hash::Hash, ops::{Deref, DerefMut}};
 
 use uuid::Uuid;
 
@@ -12,22 +12,174 @@ pub trait Resource {
     
 }
 
+/// Slot of a resource type in the dense table. Indices are never reused for
+/// another type, so systems can resolve them once and keep them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceIndex(usize);
+
 pub struct Resources {
-    resources: HashMap<TypeId, RefCell<Box<dyn Any >>>,
+    slots: Vec<Slot>,
+    indices: HashMap<TypeId, ResourceIndex>,
+    keyed: Vec<KeyedSlot>,
+    keyed_indices: HashMap<(TypeId, TypeId), KeyedIndex>,
+    // the plugin whose code is running, it owns whatever it brings into the store
+    owner: Option<&'static str>,
+    // owners whose code lives in a loaded library rather than in the host
+    libraries: HashSet<&'static str>,
+}
+
+type ReboxFn = fn(Box<dyn Any>) -> Box<dyn Any>;
+
+// boxes the value again with the vtable of the code this is compiled into
+fn rebox<T: 'static>(value: Box<dyn Any>) -> Box<dyn Any> {
+    match value.downcast::<T>() {
+        Ok(value) => value,
+        Err(value) => value,
+    }
+}
+
+struct Slot {
+    // only known for resources added with their type, not through add_entry
+    name: Option<&'static str>,
+    value: Option<RefCell<Box<dyn Any>>>,
+    // set when the value is inserted where there was none, overwriting keeps it
+    owner: Option<&'static str>,
+    // the owner of the code that boxed the current value
+    writer: Option<&'static str>,
+    // from the host's own copy of `add`, so a value boxed by a library can outlive it
+    rebox: Option<ReboxFn>,
+    // infrastructure every plugin uses, whoever inserts it first it stays with the host
+    shared: bool,
+}
+
+type PurgeFn = fn(&mut dyn Any, &str);
+
+// drops the entries `writer` boxed from a KeyedStore<K>
+fn purge_keyed<K: Hash + Eq + 'static>(store: &mut dyn Any, writer: &str) {
+    if let Some(store) = store.downcast_mut::<KeyedStore<K>>() {
+        store.retain(|_, entry| entry.writer != Some(writer));
+    }
+}
+
+struct KeyedSlot {
+    // KeyedStore<K> per (key type, resource type), `()` once its owner is gone
+    store: Box<dyn Any>,
+    owner: Option<&'static str>,
+    purge: PurgeFn,
+}
+
+struct KeyedEntry {
+    value: RefCell<Box<dyn Any>>,
+    // the owner of the code that boxed the value
+    writer: Option<&'static str>,
+}
+
+type KeyedStore<K> = HashMap<K, KeyedEntry>;
+
+/// Slot of a keyed store, resolved once by the keyed system params.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyedIndex(usize);
+
+/// Shared access to every `T` stored under a key of type `K`.
+pub struct ResKeyed<'r, K, T> {
+    store: Option<&'r KeyedStore<K>>,
+    _marker: std::marker::PhantomData<&'r T>,
+}
+
+impl<'r, K: Hash + Eq + 'static, T: Resource + 'static> ResKeyed<'r, K, T> {
+    pub fn get(&self, key: &K) -> Option<Res<'r, T>> {
+        self.store?.get(key).map(|entry| Res::new(entry.value.borrow()))
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.store.is_some_and(|store| store.contains_key(key))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &'r K> {
+        self.store.into_iter().flat_map(|store| store.keys())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&'r K, Res<'r, T>)> {
+        self.store
+            .into_iter()
+            .flat_map(|store| store.iter().map(|(key, entry)| (key, Res::new(entry.value.borrow()))))
+    }
+
+    pub fn len(&self) -> usize {
+        self.store.map(|store| store.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Mutable access to every `T` stored under a key of type `K`. Values are
+/// borrowed through `&mut self`, so a system can't hold two of them at once.
+pub struct ResKeyedMut<'r, K, T> {
+    inner: ResKeyed<'r, K, T>,
+}
+
+impl<'r, K, T> Deref for ResKeyedMut<'r, K, T> {
+    type Target = ResKeyed<'r, K, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<K: Hash + Eq + 'static, T: Resource + 'static> ResKeyedMut<'_, K, T> {
+    pub fn get_mut(&mut self, key: &K) -> Option<ResMut<'_, T>> {
+        self.inner.store?.get(key).map(|entry| ResMut::new(entry.value.borrow_mut()))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, ResMut<'_, T>)> {
+        self.inner
+            .store
+            .into_iter()
+            .flat_map(|store| store.iter().map(|(key, entry)| (key, ResMut::new(entry.value.borrow_mut()))))
+    }
 }
 
+/// Several resources borrowed mutably at once, see [`Resources::get_many_mut`].
+pub trait ResourceSet {
+    type Mut<'r>;
+    fn get_many_mut(resources: &mut Resources) -> Option<Self::Mut<'_>>;
+}
 
+fn assert_distinct(ids: &[TypeId], names: &[&'static str]) {
+    for (i, id) in ids.iter().enumerate() {
+        if ids[..i].contains(id) {
+            panic!("get_many_mut requested {} more than once", names[i]);
+        }
+    }
+}
 
-// macro_rules! impl_get_disjoint_mut_x {
-//     ($name:ident, $($type:ident, $var_name:ident),+) => {
-//         pub fn $name<$($type: Resource + 'static),+>(&mut self) -> Option<($(&mut $type,)+)> {
-//             match self.resources.get_disjoint_mut([$(&<$type>::id()),+]) {
-//                 [$(Some($var_name)),+] => Some(($($var_name.downcast_mut().unwrap(),)+)),
-//                 _ => None,
-//             }
-//         }
-//     };
-// }
+macro_rules! impl_resource_set {
+    ($($ty:ident),+) => {
+        #[allow(
+            non_snake_case,
+            reason = "Certain variable names are provided by the caller, not by us."
+        )]
+        impl<$($ty: Resource + 'static),+> ResourceSet for ($($ty,)+) {
+            type Mut<'r> = ($(&'r mut $ty,)+);
+
+            fn get_many_mut(resources: &mut Resources) -> Option<Self::Mut<'_>> {
+                assert_distinct(&[$($ty::id()),+], &[$(std::any::type_name::<$ty>()),+]);
+                let indices = [$(resources.indices.get(&$ty::id())?.0),+];
+                let [$($ty),+] = resources.slots.get_disjoint_mut(indices).ok()?;
+                Some(($($ty.value.as_mut()?.get_mut().downcast_mut::<$ty>()?,)+))
+            }
+        }
+    };
+}
+
+impl_resource_set!(T1);
+impl_resource_set!(T1, T2);
+impl_resource_set!(T1, T2, T3);
+impl_resource_set!(T1, T2, T3, T4);
+impl_resource_set!(T1, T2, T3, T4, T5);
+impl_resource_set!(T1, T2, T3, T4, T5, T6);
 
 pub struct Res<'r, T: Resource> {
     inner: Ref<'r,Box< dyn Any>>,
@@ -87,6 +239,58 @@ impl<'r, T: Resource + 'static> DerefMut for ResMut<'r, T> {
 
 
 
+/// Like [`Res`], but the resource is built with [`FromResources`] when the
+/// system is initialized and the resource is not there yet.
+pub struct ResInit<'r, T: Resource>(Res<'r, T>);
+
+impl<'r, T: Resource> ResInit<'r, T> {
+    pub fn new(inner: Res<'r, T>) -> Self {
+        ResInit(inner)
+    }
+}
+
+impl<T: Resource + 'static> Deref for ResInit<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Like [`ResMut`], but the resource is built with [`FromResources`] when the
+/// system is initialized and the resource is not there yet.
+pub struct ResMutInit<'r, T: Resource>(ResMut<'r, T>);
+
+impl<'r, T: Resource> ResMutInit<'r, T> {
+    pub fn new(inner: ResMut<'r, T>) -> Self {
+        ResMutInit(inner)
+    }
+}
+
+impl<T: Resource + 'static> Deref for ResMutInit<'_, T> {
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<T: Resource + 'static> DerefMut for ResMutInit<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Builds a resource out of the resources that are already there.
+/// Every `Default` type gets this for free.
+pub trait FromResources {
+    fn from_resources(resources: &mut Resources) -> Self;
+}
+
+impl<T: Default> FromResources for T {
+    fn from_resources(_resources: &mut Resources) -> Self {
+        T::default()
+    }
+}
+
 pub struct ResOwned<T: Resource + Clone> {
     inner: T
 }
@@ -103,45 +307,356 @@ impl<T: Resource + Clone> ResOwned<T> {
 impl Resources {
     pub fn new() -> Self {
         Resources {
-            resources: HashMap::new(),
+            slots: Vec::new(),
+            indices: HashMap::new(),
+            keyed: Vec::new(),
+            keyed_indices: HashMap::new(),
+            owner: None,
+            libraries: HashSet::new(),
+        }
+    }
+
+    pub fn owner(&self) -> Option<&'static str> {
+        self.owner
+    }
+
+    /// Sets the plugin new resources and keyed stores are attributed to and
+    /// returns the previous one.
+    pub fn set_owner(&mut self, owner: Option<&'static str>) -> Option<&'static str> {
+        std::mem::replace(&mut self.owner, owner)
+    }
+
+    /// The plugin that inserted the resource where there was none.
+    pub fn owner_of(&self, id: TypeId) -> Option<&'static str> {
+        self.index_of_id(id).and_then(|index| self.slots[index.0].owner)
+    }
+
+    pub(crate) fn set_owner_of(&mut self, id: TypeId, owner: Option<&'static str>) {
+        if let Some(index) = self.index_of_id(id) {
+            self.slots[index.0].owner = owner;
+        }
+    }
+
+    /// The plugin that last inserted the resource.
+    pub fn writer_of(&self, id: TypeId) -> Option<&'static str> {
+        self.index_of_id(id)
+            .and_then(|index| self.slots[index.0].value.as_ref().and(self.slots[index.0].writer))
+    }
+
+    /// The present resources `owner` introduced.
+    pub fn owned_by(&self, owner: &str) -> Vec<TypeId> {
+        self.ids().filter(|id| self.owner_of(*id) == Some(owner)).collect()
+    }
+
+    /// Marks `owner` as code from a loaded library until it is released.
+    pub fn add_library(&mut self, owner: &'static str) {
+        self.libraries.insert(owner);
+    }
+
+    fn shared_written_by<'a>(&'a self, owner: &'a str) -> impl Iterator<Item = &'a Slot> + 'a {
+        self.slots
+            .iter()
+            .filter(move |slot| slot.value.is_some() && slot.writer == Some(owner) && slot.owner != Some(owner))
+    }
+
+    /// Resources `owner` overwrote but did not introduce whose value the host can't box again.
+    pub fn unmovable(&self, owner: &str) -> Vec<&'static str> {
+        self.shared_written_by(owner)
+            .filter(|slot| slot.rebox.is_none())
+            .map(|slot| slot.name.unwrap_or("<unnamed>"))
+            .collect()
+    }
+
+    /// Forgets everything pointing into the code of `owner` once its resources
+    /// were removed: the values it overwrote are boxed again by the host, the
+    /// type names it gave are cleared, its keyed stores are dropped and the
+    /// entries it boxed into other stores are removed. Dropped stores keep their
+    /// index taken, a store of the same types created later gets a new one.
+    pub fn release(&mut self, owner: &str) {
+        for slot in &mut self.slots {
+            if slot.owner == Some(owner) {
+                slot.owner = None;
+                slot.writer = None;
+                if slot.rebox.is_none() {
+                    slot.name = None;
+                }
+                continue;
+            }
+            if slot.writer != Some(owner) {
+                continue;
+            }
+            slot.writer = None;
+            if let (Some(rebox), Some(value)) = (slot.rebox, slot.value.take()) {
+                slot.value = Some(RefCell::new(rebox(value.into_inner())));
+            }
+        }
+        self.libraries.remove(owner);
+        let keyed = &mut self.keyed;
+        self.keyed_indices.retain(|_, index| {
+            let slot = &mut keyed[index.0];
+            if slot.owner != Some(owner) {
+                return true;
+            }
+            slot.store = Box::new(());
+            slot.owner = None;
+            false
+        });
+        // a dropped store's purge may live in unloaded code, only live ones are visited
+        for index in self.keyed_indices.values() {
+            let slot = &mut self.keyed[index.0];
+            (slot.purge)(slot.store.as_mut(), owner);
+        }
+    }
+
+    // slots are only allocated for types that were inserted at least once
+    fn allocate(&mut self, id: TypeId) -> ResourceIndex {
+        if let Some(index) = self.indices.get(&id) {
+            return *index;
         }
+        let index = ResourceIndex(self.slots.len());
+        self.slots.push(Slot {
+            name: None,
+            value: None,
+            owner: None,
+            writer: None,
+            rebox: None,
+            shared: false,
+        });
+        self.indices.insert(id, index);
+        index
+    }
+
+    pub fn index_of_id(&self, id: TypeId) -> Option<ResourceIndex> {
+        self.indices.get(&id).copied()
+    }
+
+    pub fn index_of<T: Resource + 'static>(&self) -> Option<ResourceIndex> {
+        self.index_of_id(T::id())
+    }
+
+    /// The index of `T`, looked up until the resource has been inserted once and cached in `cached` after that.
+    pub fn resolve_index<T: Resource + 'static>(&self, cached: &mut Option<ResourceIndex>) -> Option<ResourceIndex> {
+        if cached.is_none() {
+            *cached = self.index_of::<T>();
+        }
+        *cached
+    }
+
+    fn slot(&self, id: &TypeId) -> Option<&RefCell<Box<dyn Any>>> {
+        self.indices.get(id).and_then(|index| self.slots[index.0].value.as_ref())
+    }
+
+    fn in_library(&self) -> bool {
+        self.owner.is_some_and(|owner| self.libraries.contains(owner))
     }
 
     pub fn add<T: Resource + 'static>(&mut self, resource: T) {
-        self.resources.insert(T::id(), RefCell::new(Box::new(resource)));
+        let index = self.allocate(T::id());
+        let in_library = self.in_library();
+        let slot = &mut self.slots[index.0];
+        // a library's strings and functions go away with it, the host's are kept
+        if !in_library {
+            slot.name = Some(std::any::type_name::<T>());
+            slot.rebox = Some(rebox::<T>);
+        } else if slot.name.is_none() {
+            slot.name = Some(std::any::type_name::<T>());
+        }
+        self.add_entry((T::id(), RefCell::new(Box::new(resource))));
+    }
+
+    pub fn type_name(&self, id: TypeId) -> Option<&'static str> {
+        self.index_of_id(id).and_then(|index| self.slots[index.0].name)
+    }
+
+    /// The type ids of every resource currently present.
+    pub fn ids(&self) -> impl Iterator<Item = TypeId> + '_ {
+        self.indices
+            .iter()
+            .filter(|(_, index)| self.slots[index.0].value.is_some())
+            .map(|(id, _)| *id)
     }
     pub fn add_if_not_present<T: Resource + 'static>(&mut self, resource: T) {
-        if !self.resources.contains_key(&T::id()) { 
+        if !self.contains::<T>() { 
             self.add(resource);
         }
     }
 
+    /// Inserts `T` built with [`FromResources`] unless it is already present.
+    pub fn init<T: Resource + FromResources + 'static>(&mut self) -> ResourceIndex {
+        if !self.contains::<T>() {
+            let resource = T::from_resources(self);
+            self.add(resource);
+        }
+        self.allocate(T::id())
+    }
+
+    /// Marks `T` as infrastructure of the host: whichever plugin inserts it
+    /// first, it is not removed with that plugin. A library can't share a type
+    /// the host never saw, it may be one the library defines itself.
+    pub fn share<T: Resource + 'static>(&mut self) -> ResourceIndex {
+        let index = self.allocate(T::id());
+        let in_library = self.in_library();
+        let slot = &mut self.slots[index.0];
+        if in_library && slot.rebox.is_none() {
+            return index;
+        }
+        if !in_library {
+            slot.name = Some(std::any::type_name::<T>());
+            slot.rebox = Some(rebox::<T>);
+        }
+        slot.shared = true;
+        slot.owner = None;
+        index
+    }
+
+    /// [`Resources::init`] for a type shared with [`Resources::share`].
+    pub fn init_shared<T: Resource + FromResources + 'static>(&mut self) -> ResourceIndex {
+        self.share::<T>();
+        self.init::<T>()
+    }
+
+    pub fn contains<T: Resource + 'static>(&self) -> bool {
+        self.contains_id(T::id())
+    }
+
+    pub fn contains_id(&self, id: TypeId) -> bool {
+        self.slot(&id).is_some()
+    }
+
     pub fn remove<T: Resource + 'static>(&mut self) -> Option<T>{
-        let some = self.resources.remove(&T::id());
+        let index = *self.indices.get(&T::id())?;
+        let some = self.slots[index.0].value.take();
         let r = some.and_then(|any| ((any).into_inner().downcast::<T>().ok())).and_then(|t| Some(*t));
         r
     }
 
+    /// Resolves the store of `T` keyed by `K`, creating an empty one if needed.
+    pub fn keyed_index<K: Hash + Eq + 'static, T: Resource + 'static>(&mut self) -> KeyedIndex {
+        let id = (TypeId::of::<K>(), T::id());
+        if let Some(index) = self.keyed_indices.get(&id) {
+            return *index;
+        }
+        let index = KeyedIndex(self.keyed.len());
+        self.keyed.push(KeyedSlot {
+            store: Box::new(KeyedStore::<K>::new()),
+            owner: self.owner,
+            purge: purge_keyed::<K>,
+        });
+        self.keyed_indices.insert(id, index);
+        index
+    }
+
+    fn keyed_store<K: Hash + Eq + 'static>(&self, index: KeyedIndex) -> Option<&KeyedStore<K>> {
+        self.keyed.get(index.0).and_then(|slot| slot.store.downcast_ref::<KeyedStore<K>>())
+    }
+
+    pub fn add_keyed<K: Hash + Eq + 'static, T: Resource + 'static>(&mut self, key: K, resource: T) {
+        let index = self.keyed_index::<K, T>();
+        let entry = KeyedEntry {
+            value: RefCell::new(Box::new(resource)),
+            writer: self.owner,
+        };
+        self.keyed[index.0]
+            .store
+            .downcast_mut::<KeyedStore<K>>()
+            .unwrap()
+            .insert(key, entry);
+    }
+
+    pub fn remove_keyed<K: Hash + Eq + 'static, T: Resource + 'static>(&mut self, key: &K) -> Option<T> {
+        let index = *self.keyed_indices.get(&(TypeId::of::<K>(), T::id()))?;
+        self.keyed[index.0]
+            .store
+            .downcast_mut::<KeyedStore<K>>()
+            .unwrap()
+            .remove(key)
+            .and_then(|entry| entry.value.into_inner().downcast::<T>().ok())
+            .map(|t| *t)
+    }
+
+    pub fn keyed<K: Hash + Eq + 'static, T: Resource + 'static>(&self) -> ResKeyed<'_, K, T> {
+        let index = self.keyed_indices.get(&(TypeId::of::<K>(), T::id()));
+        ResKeyed {
+            store: index.and_then(|index| self.keyed_store(*index)),
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    pub fn keyed_indexed<K: Hash + Eq + 'static, T: Resource + 'static>(&self, index: KeyedIndex) -> ResKeyed<'_, K, T> {
+        ResKeyed {
+            store: self.keyed_store(index),
+            _marker: std::marker::PhantomData,
+        }
+    }
 
+    pub fn keyed_indexed_mut<K: Hash + Eq + 'static, T: Resource + 'static>(&self, index: KeyedIndex) -> ResKeyedMut<'_, K, T> {
+        ResKeyedMut {
+            inner: self.keyed_indexed(index),
+        }
+    }
+
+    pub fn get_keyed<K: Hash + Eq + 'static, T: Resource + 'static>(&self, key: &K) -> Option<Res<'_, T>> {
+        self.keyed::<K, T>().get(key)
+    }
+
+    pub fn get_keyed_mut<K: Hash + Eq + 'static, T: Resource + 'static>(&mut self, key: &K) -> Option<ResMut<'_, T>> {
+        let index = *self.keyed_indices.get(&(TypeId::of::<K>(), T::id()))?;
+        self.keyed_store::<K>(index)?
+            .get(key)
+            .map(|entry| ResMut::new(entry.value.borrow_mut()))
+    }
 
     pub fn add_entry(&mut self, entry: (TypeId, RefCell<Box<dyn Any>>)) {
-        self.resources.insert(entry.0, entry.1);
+        let index = self.allocate(entry.0);
+        let slot = &mut self.slots[index.0];
+        if slot.value.is_none() && !slot.shared {
+            slot.owner = self.owner;
+        }
+        slot.writer = self.owner;
+        slot.value = Some(entry.1);
     }
 
-    pub fn get<T: Resource + 'static>(&self) -> Option<Res<T>> {
-        self.resources
-            .get(&T::id())
+    pub fn get_entry(&self, id: TypeId) -> Option<Ref<'_, Box<dyn Any>>> {
+        self.slot(&id).map(|cell| cell.borrow())
+    }
+
+    pub fn remove_entry(&mut self, id: TypeId) -> Option<Box<dyn Any>> {
+        let index = *self.indices.get(&id)?;
+        self.slots[index.0].value.take().map(|cell| cell.into_inner())
+    }
+
+    pub fn get<T: Resource + 'static>(&self) -> Option<Res<'_, T>> {
+        self.slot(&T::id())
             .map(|cell| cell.borrow())
             .map(|c| Res::new(c))
     }
 
-    pub fn get_mut<T: Resource + 'static>(&self) -> Option<ResMut<T>> {
-        self.resources
-            .get(&T::id())
+    pub fn get_mut<T: Resource + 'static>(&self) -> Option<ResMut<'_, T>> {
+        self.slot(&T::id())
             .map(|cell| cell.borrow_mut())
             .map(|c| ResMut::new(c))
     }
 
+    /// Mutable access to several distinct resources, `None` if any is missing.
+    /// Panics if a type is requested twice.
+    pub fn get_many_mut<S: ResourceSet>(&mut self) -> Option<S::Mut<'_>> {
+        S::get_many_mut(self)
+    }
+
+    pub fn get_indexed<T: Resource + 'static>(&self, index: ResourceIndex) -> Option<Res<'_, T>> {
+        self.slots
+            .get(index.0)
+            .and_then(|slot| slot.value.as_ref())
+            .map(|cell| Res::new(cell.borrow()))
+    }
+
+    pub fn get_indexed_mut<T: Resource + 'static>(&self, index: ResourceIndex) -> Option<ResMut<'_, T>> {
+        self.slots
+            .get(index.0)
+            .and_then(|slot| slot.value.as_ref())
+            .map(|cell| ResMut::new(cell.borrow_mut()))
+    }
+
     // pub fn observe<T: Clone + 'static>(&mut self) -> Receiver<T> {
     //     self.get_mut::<ChannelResource<T>>()
     //         .map(|channel| channel.subscribe())
@@ -165,25 +680,6 @@ impl Resources {
     //         })
     // }
 
-    // impl_get_disjoint_mut_x!(get_disjoint_mut_2, T1, t1, T2, t2);
-    // impl_get_disjoint_mut_x!(get_disjoint_mut_3, T1, t1, T2, t2, T3, t3);
-    // impl_get_disjoint_mut_x!(get_disjoint_mut_4, T1, t1, T2, t2, T3, t3, T4, t4);
-    // impl_get_disjoint_mut_x!(get_disjoint_mut_5, T1, t1, T2, t2, T3, t3, T4, t4, T5, t5);
-    // impl_get_disjoint_mut_x!(
-    //     get_disjoint_mut_6,
-    //     T1,
-    //     t1,
-    //     T2,
-    //     t2,
-    //     T3,
-    //     t3,
-    //     T4,
-    //     t4,
-    //     T5,
-    //     t5,
-    //     T6,
-    //     t6
-    // );
 }
 
 
@@ -0,0 +1,100 @@
+use std::{future::Future, marker::PhantomData};
+
+use crate::{
+    runtime::Runtime,
+    storage::Resources,
+    system::{
+        IntoSystem, System, SystemAccess, SystemParam,
+        commands::{Command, CommandSender},
+    },
+};
+
+pub struct AsyncMarker;
+
+pub struct AsyncFunctionSystem<F, Fut, P: SystemParam> {
+    f: F,
+    state: Option<(P::State, CommandSender)>,
+    marker: PhantomData<fn() -> (Fut, P)>,
+}
+
+fn spawn<Fut>(resources: &Resources, sender: CommandSender, future: Fut)
+where
+    Fut: Future + Send + 'static,
+    Fut::Output: Command + Send + 'static,
+{
+    // dropping the future would silently lose whatever the system meant to do
+    let runtime = resources
+        .get::<Runtime>()
+        .expect("async systems need the Runtime resource, App::run adds it before the first frame");
+    // counted against the plugin, its code runs until the command is sent
+    runtime.spawn_for(resources.owner(), async move {
+        sender.send(future.await);
+    });
+}
+
+macro_rules! impl_async_system_function {
+
+    ($($ty:ident),*) => {
+          #[allow(
+            non_snake_case,
+            reason = "Certain variable names are provided by the caller, not by us."
+        )]
+        impl<F: FnMut($($ty,)*) -> Fut, Fut, $($ty,)*> System for AsyncFunctionSystem<F, Fut, ($($ty,)*)>
+        where
+            Fut: Future + Send + 'static,
+            Fut::Output: Command + Send + 'static,
+            $($ty : SystemParam,)*
+            for<'a, 'b> &'a mut F: FnMut($($ty,)*) -> Fut + FnMut($(<$ty as SystemParam>::Item<'b>,)*) -> Fut {
+            fn run(&mut self, resources: &mut Resources) {
+                <($($ty,)*) as SystemParam>::prepare(resources);
+                let (state, sender) = self.state.get_or_insert_with(|| {
+                    (
+                        <($($ty,)*) as SystemParam>::init_state(resources),
+                        CommandSender::init_state(resources),
+                    )
+                });
+                let sender = sender.clone();
+                let ($($ty,)*) = <($($ty,)*) as SystemParam>::param(state, resources);
+                fn call_inner<Fut, $($ty,)*>(mut f: impl FnMut($($ty,)*) -> Fut, $($ty: $ty,)*) -> Fut {
+                    f($($ty,)*)
+                }
+                let future = call_inner(&mut self.f, $($ty,)*);
+                spawn(resources, sender, future);
+            }
+
+            fn name(&self) -> &'static str {
+                std::any::type_name::<F>()
+            }
+
+            fn access(&self) -> SystemAccess {
+                let mut _access = SystemAccess::default();
+                $($ty::access(&mut _access);)*
+                _access.read::<Runtime>();
+                _access
+            }
+        }
+
+        #[allow(
+            non_snake_case,
+            reason = "Certain variable names are provided by the caller, not by us."
+        )]
+        impl<F: FnMut($($ty,)*) -> Fut, Fut, $($ty,)*> IntoSystem<(AsyncMarker, Fut, $($ty,)*)> for F
+        where
+            Fut: Future + Send + 'static,
+            Fut::Output: Command + Send + 'static,
+            $($ty : SystemParam,)*
+            for<'a, 'b> &'a mut F: FnMut($($ty,)*) -> Fut + FnMut($(<$ty as SystemParam>::Item<'b>,)*) -> Fut {
+            type System = AsyncFunctionSystem<F, Fut, ($($ty,)*)>;
+            fn into_system(self) -> Self::System {
+                AsyncFunctionSystem { f: self, state: None, marker: PhantomData }
+            }
+        }
+    };
+}
+
+impl_async_system_function!();
+impl_async_system_function!(In1);
+impl_async_system_function!(In1, In2);
+impl_async_system_function!(In1, In2, In3);
+impl_async_system_function!(In1, In2, In3, In4);
+impl_async_system_function!(In1, In2, In3, In4, In5);
@@ -1,6 +1,6 @@
-use std::{any::{Any, TypeId}, cell::RefCell, marker::PhantomData};
+use std::{any::{Any, TypeId}, cell::RefCell, fmt::Display, hash::Hash, marker::PhantomData, path::PathBuf, sync::mpsc::{self, Receiver, Sender}, time::Duration};
 
-use crate::{storage::{ResMut, Resource, Resources}, system::{scheduler::{self, Scheduler}, SystemParam}};
+use crate::{dynamic::PluginRequest, storage::{ResMut, Resource, ResourceIndex, Resources}, time::Delay, system::{scheduler::{self, Label, Scheduler, StoredSystem, SystemId}, undo::{BeginTransaction, CommitTransaction, Record, Redo, ReversibleCommand, Undo}, IntoResult, IntoStoredSystem, IntoStoredSystems, IntoSystem, SystemAccess, SystemParam}};
 
 
 pub trait BoxedCommand {
@@ -23,6 +23,70 @@ impl<T> Command for Box<T> where T: ?Sized + Command {
     }
 }
 
+impl Command for () {
+    fn execute(self, _scheduler: &mut Scheduler) {}
+}
+
+pub trait TryCommand {
+    type Error: Display;
+    fn try_execute(self, scheduler: &mut Scheduler) -> Result<(), Self::Error>;
+}
+
+pub struct Fallible<T>(pub T);
+
+impl<T: TryCommand> Command for Fallible<T> {
+    fn execute(self, scheduler: &mut Scheduler) {
+        if let Err(e) = self.0.try_execute(scheduler) {
+            scheduler.report_command_error(std::any::type_name::<T>(), e.to_string());
+        }
+    }
+}
+
+pub struct FnCommand<F>(pub F);
+
+impl<F, R> Command for FnCommand<F>
+where
+    F: FnOnce(&mut Scheduler) -> R,
+    R: IntoResult,
+{
+    fn execute(self, scheduler: &mut Scheduler) {
+        if let Err(e) = (self.0)(scheduler).into_result() {
+            scheduler.report_command_error(std::any::type_name::<F>(), e);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CommandError {
+    pub command: &'static str,
+    pub source: Option<&'static str>,
+    pub message: String,
+}
+
+impl Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "command {} queued by {} failed: {}",
+            self.command,
+            self.source.unwrap_or("<unknown>"),
+            self.message
+        )
+    }
+}
+
+pub struct CommandErrorHandler(pub Box<dyn FnMut(&CommandError)>);
+
+impl CommandErrorHandler {
+    pub fn new<F: FnMut(&CommandError) + 'static>(handler: F) -> Self {
+        CommandErrorHandler(Box::new(handler))
+    }
+
+    pub fn log() -> Self {
+        Self::new(|error| log::error!("{}", error))
+    }
+}
+
 pub struct AddResource {
     pub id: TypeId,
     pub resource: RefCell<Box<dyn Any>>,
@@ -35,6 +99,16 @@ impl Command for AddResource {
 }
 
 
+pub struct InsertResource<T> {
+    pub resource: T,
+}
+
+impl<T: Resource + 'static> Command for InsertResource<T> {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.add_resource(self.resource);
+    }
+}
+
 pub struct RemoveResource<T> {
     pub marker: PhantomData<T>
 }
@@ -51,33 +125,214 @@ impl<T> RemoveResource<T> {
     }
 }
 
+pub struct InsertKeyedResource<K, T> {
+    pub key: K,
+    pub resource: T,
+}
+
+impl<K: Hash + Eq + 'static, T: Resource + 'static> Command for InsertKeyedResource<K, T> {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.add_keyed_resource(self.key, self.resource);
+    }
+}
+
+pub struct RemoveKeyedResource<K, T> {
+    pub key: K,
+    pub marker: PhantomData<T>,
+}
+
+impl<K: Hash + Eq + 'static, T: Resource + 'static> Command for RemoveKeyedResource<K, T> {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.remove_keyed_resource::<K, T>(&self.key);
+    }
+}
+
+pub struct RunSystem {
+    pub id: SystemId,
+}
+
+impl Command for RunSystem {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.run_system(self.id);
+    }
+}
+
+pub struct RegisterSystem {
+    pub id: SystemId,
+    pub system: StoredSystem,
+}
+
+impl Command for RegisterSystem {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.insert_system(self.id, self.system);
+    }
+}
+
+pub struct AddSystems {
+    pub label: Box<dyn Label>,
+    pub systems: Vec<StoredSystem>,
+}
+
+impl Command for AddSystems {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.add_stored_systems(self.label.as_ref(), self.systems);
+    }
+}
+
+pub struct RunSchedule {
+    pub label: Box<dyn Label>,
+}
+
+impl Command for RunSchedule {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.run_schedule(self.label.as_ref());
+    }
+}
+
+pub struct RunScheduleWith<T> {
+    pub label: Box<dyn Label>,
+    pub input: T,
+}
+
+impl<T: Resource + 'static> Command for RunScheduleWith<T> {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.run_schedule_with(self.label.as_ref(), self.input);
+    }
+}
+
+pub struct Trigger<E> {
+    pub event: E,
+}
+
+impl<E: 'static> Command for Trigger<E> {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.trigger(self.event);
+    }
+}
+
+pub struct QueuedCommand {
+    pub command: Box<dyn Command>,
+    pub source: Option<&'static str>,
+    // the plugin that queued it, the command runs as that plugin
+    pub plugin: Option<&'static str>,
+}
+
 pub struct CommandList {
-    commands: Vec<Box<dyn Command>>,
+    commands: Vec<QueuedCommand>,
+    // the system currently running, commands queued through `Commands` are attributed to it
+    source: Option<&'static str>,
+}
+
+impl Default for CommandList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CommandList {
-    pub fn take(&mut self) -> Vec<Box<dyn Command>> {
+    pub fn new() -> Self {
+        CommandList {
+            commands: vec![],
+            source: None,
+        }
+    }
+
+    /// Sets the system commands are attributed to and returns the previous one.
+    pub fn set_source(&mut self, source: Option<&'static str>) -> Option<&'static str> {
+        std::mem::replace(&mut self.source, source)
+    }
+
+    pub fn push(&mut self, command: QueuedCommand) {
+        self.commands.push(command);
+    }
+
+    pub fn take(&mut self) -> Vec<QueuedCommand> {
         let commands = std::mem::replace(&mut self.commands, Vec::new());
         commands
     }
 }
 
+pub type SendCommand = Box<dyn Command + Send>;
+
+pub struct CommandQueue {
+    sender: Sender<(SendCommand, Option<&'static str>)>,
+    receiver: Receiver<(SendCommand, Option<&'static str>)>,
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandQueue {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        CommandQueue { sender, receiver }
+    }
+
+    pub fn sender(&self) -> CommandSender {
+        self.sender_for(None)
+    }
+
+    /// A sender whose commands run as `plugin`, like the ones it queues through `Commands`.
+    pub fn sender_for(&self, plugin: Option<&'static str>) -> CommandSender {
+        CommandSender {
+            sender: self.sender.clone(),
+            plugin,
+        }
+    }
+
+    pub fn drain(&self) -> Vec<QueuedCommand> {
+        self.receiver
+            .try_iter()
+            .map(|(command, plugin)| QueuedCommand {
+                command,
+                source: None,
+                plugin,
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct CommandSender {
+    sender: Sender<(SendCommand, Option<&'static str>)>,
+    plugin: Option<&'static str>,
+}
+
+impl CommandSender {
+    pub fn send<T: Command + Send + 'static>(&self, command: T) -> bool {
+        self.sender.send((Box::new(command), self.plugin)).is_ok()
+    }
+
+    pub fn insert_resource<T: Resource + Send + 'static>(&self, resource: T) -> bool {
+        self.send(InsertResource { resource })
+    }
+
+    pub fn run_system(&self, id: SystemId) -> bool {
+        self.send(RunSystem { id })
+    }
+}
+
 pub struct Commands<'a> {
     list: ResMut<'a, CommandList>,
+    source: Option<&'static str>,
+    plugin: Option<&'static str>,
 }
 
 impl Commands<'_> {
     pub fn from_resources(resources: &Resources) -> Commands {
+        let list: ResMut<CommandList> = resources.get_mut().unwrap();
         Commands {
-            list: resources.get_mut().unwrap(),
+            source: list.source,
+            plugin: resources.owner(),
+            list,
         }
     }
 
     pub fn insert_resource<T: Resource + 'static>(&mut self, resource: T) {
-        self.add_command(AddResource{
-            id: T::id(),
-            resource: RefCell::new(Box::new(resource)),
-        });
+        self.add_command(InsertResource { resource });
     }
 
     pub fn remove_resource<T: Resource + 'static>(&mut self) {
@@ -85,8 +340,118 @@ impl Commands<'_> {
     }
 
 
+    pub fn insert_keyed_resource<K: Hash + Eq + 'static, T: Resource + 'static>(&mut self, key: K, resource: T) {
+        self.add_command(InsertKeyedResource { key, resource });
+    }
+
+    pub fn remove_keyed_resource<K: Hash + Eq + 'static, T: Resource + 'static>(&mut self, key: K) {
+        self.add_command(RemoveKeyedResource::<K, T> {
+            key,
+            marker: PhantomData,
+        });
+    }
+
+    pub fn run_system(&mut self, id: SystemId) {
+        self.add_command(RunSystem { id });
+    }
+
+    pub fn register_system<T: IntoSystem<I>, I>(&mut self, system: T) -> SystemId
+    where
+        T::System: 'static,
+    {
+        let id = SystemId::new();
+        self.add_command(RegisterSystem {
+            id,
+            system: system.into_system().into_stored_system(),
+        });
+        id
+    }
+
+    pub fn add_systems<T: IntoStoredSystems<I>, I>(&mut self, label: impl Label + 'static, systems: T) {
+        self.add_command(AddSystems {
+            label: Box::new(label),
+            systems: systems.into_stored_systems().collect(),
+        });
+    }
+
+    pub fn run_schedule(&mut self, label: impl Label + 'static) {
+        self.add_command(RunSchedule {
+            label: Box::new(label),
+        });
+    }
+
+    pub fn run_schedule_with<T: Resource + 'static>(&mut self, label: impl Label + 'static, input: T) {
+        self.add_command(RunScheduleWith {
+            label: Box::new(label),
+            input,
+        });
+    }
+
+    pub fn add<F, R>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Scheduler) -> R + 'static,
+        R: IntoResult,
+    {
+        self.add_command(FnCommand(f));
+    }
+
+    pub fn trigger<E: 'static>(&mut self, event: E) {
+        self.add_command(Trigger { event });
+    }
+
+    pub fn after<T: Command + 'static>(&mut self, duration: Duration, command: T) {
+        let command = QueuedCommand {
+            command: Box::new(command),
+            source: self.source,
+            plugin: self.plugin,
+        };
+        self.add_command(Delay { duration, command });
+    }
+
+    /// Runs the command during the flush and records its inverse for [`Commands::undo`].
+    pub fn add_reversible<T: ReversibleCommand + 'static>(&mut self, command: T) {
+        self.add_command(Record(Box::new(command)));
+    }
+
+    pub fn begin_transaction(&mut self, name: impl Into<String>) {
+        self.add_command(BeginTransaction(name.into()));
+    }
+
+    pub fn commit_transaction(&mut self) {
+        self.add_command(CommitTransaction);
+    }
+
+    pub fn undo(&mut self) {
+        self.add_command(Undo);
+    }
+
+    pub fn redo(&mut self) {
+        self.add_command(Redo);
+    }
+
+    /// Asks the app to load a plugin library once the frame is done.
+    pub fn load_plugin(&mut self, path: impl Into<PathBuf>) {
+        self.add_command(PluginRequest::Load(path.into()));
+    }
+
+    pub fn unload_plugin(&mut self, name: impl Into<String>) {
+        self.add_command(PluginRequest::Unload(name.into()));
+    }
+
+    pub fn reload_plugin(&mut self, name: impl Into<String>) {
+        self.add_command(PluginRequest::Reload(name.into()));
+    }
+
+    pub fn try_command<T: TryCommand + 'static>(&mut self, command: T) {
+        self.add_command(Fallible(command));
+    }
+
     pub fn add_command<T: Command + 'static>(&mut self, command: T) {
-        self.list.commands.push(Box::new(command));
+        self.list.commands.push(QueuedCommand {
+            command: Box::new(command),
+            source: self.source,
+            plugin: self.plugin,
+        });
     }
 
 }
@@ -96,12 +461,40 @@ impl Commands<'_> {
 
 impl<'a> SystemParam for Commands<'a> {
     type Item<'new> = Commands<'new>;
+    type State = ResourceIndex;
+
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.init_shared::<CommandList>()
+    }
 
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-        Self::from_resources(resources)
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        let list: ResMut<CommandList> = resources.get_indexed_mut(*state).unwrap();
+        Commands {
+            source: list.source,
+            plugin: resources.owner(),
+            list,
+        }
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.write::<CommandList>();
     }
+}
+
+impl SystemParam for CommandSender {
+    type Item<'new> = CommandSender;
+    type State = CommandSender;
 
-    fn prepare<'r>(_resources: &'r mut Resources) {
-        _resources.add_if_not_present(CommandList { commands: vec![] });
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.init_shared::<CommandQueue>();
+        resources.get::<CommandQueue>().unwrap().sender_for(resources.owner())
     }
-}
\ No newline at end of file
+
+    fn param<'r>(state: &'r mut Self::State, _resources: &'r Resources) -> Self::Item<'r> {
+        state.clone()
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.read::<CommandQueue>();
+    }
+}
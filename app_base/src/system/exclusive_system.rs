@@ -0,0 +1,139 @@
+use std::marker::PhantomData;
+
+use crate::{
+    storage::Resources,
+    system::{IntoSystem, System, SystemAccess, pipe::{IntoIoSystem, IoSystem}, scheduler::Scheduler},
+};
+
+pub struct ExclusiveMarker;
+
+pub struct ExclusiveFunctionSystem<F, In> {
+    f: F,
+    marker: PhantomData<fn() -> In>,
+}
+
+impl<F: FnMut(&mut Scheduler)> System for ExclusiveFunctionSystem<F, Scheduler> {
+    fn run(&mut self, _resources: &mut Resources) {
+        panic!(
+            "exclusive system {} can only be run through run_exclusive",
+            System::name(self)
+        );
+    }
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<F>()
+    }
+
+    fn access(&self) -> SystemAccess {
+        let mut access = SystemAccess::default();
+        access.write::<Scheduler>();
+        access
+    }
+
+    fn is_exclusive(&self) -> bool {
+        true
+    }
+
+    fn run_exclusive(&mut self, scheduler: &mut Scheduler) {
+        (self.f)(scheduler);
+    }
+}
+
+impl<F: FnMut(&mut Resources)> System for ExclusiveFunctionSystem<F, Resources> {
+    fn run(&mut self, resources: &mut Resources) {
+        (self.f)(resources);
+    }
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<F>()
+    }
+
+    fn access(&self) -> SystemAccess {
+        let mut access = SystemAccess::default();
+        access.write::<Resources>();
+        access
+    }
+}
+
+// lets exclusive systems start a pipe, the pipe then runs exclusively as a whole
+impl<F: FnMut(&mut Scheduler)> IoSystem for ExclusiveFunctionSystem<F, Scheduler> {
+    type In = ();
+    type Out = ();
+
+    fn run_io(&mut self, _input: (), resources: &mut Resources) {
+        System::run(self, resources);
+    }
+
+    fn name(&self) -> &'static str {
+        System::name(self)
+    }
+
+    fn access(&self) -> SystemAccess {
+        System::access(self)
+    }
+
+    fn is_exclusive(&self) -> bool {
+        true
+    }
+
+    fn run_io_exclusive(&mut self, _input: (), scheduler: &mut Scheduler) {
+        (self.f)(scheduler);
+    }
+}
+
+impl<F: FnMut(&mut Resources)> IoSystem for ExclusiveFunctionSystem<F, Resources> {
+    type In = ();
+    type Out = ();
+
+    fn run_io(&mut self, _input: (), resources: &mut Resources) {
+        (self.f)(resources);
+    }
+
+    fn name(&self) -> &'static str {
+        System::name(self)
+    }
+
+    fn access(&self) -> SystemAccess {
+        System::access(self)
+    }
+}
+
+impl<F: FnMut(&mut Scheduler)> IntoIoSystem<(ExclusiveMarker, Scheduler)> for F {
+    type System = ExclusiveFunctionSystem<F, Scheduler>;
+    fn into_io_system(self) -> Self::System {
+        ExclusiveFunctionSystem {
+            f: self,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<F: FnMut(&mut Resources)> IntoIoSystem<(ExclusiveMarker, Resources)> for F {
+    type System = ExclusiveFunctionSystem<F, Resources>;
+    fn into_io_system(self) -> Self::System {
+        ExclusiveFunctionSystem {
+            f: self,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<F: FnMut(&mut Scheduler)> IntoSystem<(ExclusiveMarker, Scheduler)> for F {
+    type System = ExclusiveFunctionSystem<F, Scheduler>;
+    fn into_system(self) -> Self::System {
+        ExclusiveFunctionSystem {
+            f: self,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<F: FnMut(&mut Resources)> IntoSystem<(ExclusiveMarker, Resources)> for F {
+    type System = ExclusiveFunctionSystem<F, Resources>;
+    fn into_system(self) -> Self::System {
+        ExclusiveFunctionSystem {
+            f: self,
+            marker: PhantomData,
+        }
+    }
+}
@@ -1,29 +1,22 @@
 use std::marker::PhantomData;
 
-use crate::{storage::Resources, system::{IntoSystem, System, SystemParam}};
+use crate::{storage::Resources, system::{IntoResult, IntoSystem, System, SystemAccess, SystemParam, pipe::{IntoIoSystem, IoSystem}, report_system_error}};
 
 
 
 
-pub struct FunctionSystem<F, In>
+pub struct FunctionSystem<F, Out, P: SystemParam>
 {
     f: F,
-    marker: PhantomData<fn() -> In>
+    state: Option<P::State>,
+    marker: PhantomData<fn() -> (Out, P)>
 }
 
-
-// impl<F: FnMut()> System for FunctionSystem<F, ()> {
-//     fn run(&mut self, _resources: &Resources) {
-//         (self.f)();
-//     }
-// }
-
-// impl<F: FnMut(In1), In1: SystemParam> System for FunctionSystem<F, (In1)> {
-//     fn run(&mut self, resources: &Resources, in1: In1) {
-//         (self.f)(In1::param(resources));
-//     }
-// }
-
+impl<F, Out, P: SystemParam> FunctionSystem<F, Out, P> {
+    fn new(f: F) -> Self {
+        FunctionSystem { f, state: None, marker: PhantomData }
+    }
+}
 
 
 macro_rules! impl_system_function {
@@ -33,19 +26,57 @@ macro_rules! impl_system_function {
             non_snake_case,
             reason = "Certain variable names are provided by the caller, not by us."
         )]
-        impl<F: FnMut($($ty,)*), $($ty,)*> System for FunctionSystem<F, ($($ty),*)>
+        impl<F: FnMut($($ty,)*) -> Out, Out, $($ty,)*> IoSystem for FunctionSystem<F, Out, ($($ty,)*)>
         where 
             $($ty : SystemParam,)*
-            for<'a, 'b> &'a mut F: FnMut($($ty,)*) + FnMut($(<$ty as SystemParam>::Item<'b>,)*) { 
-            fn run(&mut self, _resources: &mut Resources) {
-               
-                $({
-                    $ty::prepare(_resources);
-                })*
-                fn call_inner<$($ty,)*>(mut f: impl FnMut($($ty,)*), $($ty: $ty,)*) {
-                    f($($ty,)*);
+            for<'a, 'b> &'a mut F: FnMut($($ty,)*) -> Out + FnMut($(<$ty as SystemParam>::Item<'b>,)*) -> Out { 
+            type In = ();
+            type Out = Out;
+
+            fn run_io(&mut self, _input: (), resources: &mut Resources) -> Out {
+                <($($ty,)*) as SystemParam>::prepare(resources);
+                let state = self
+                    .state
+                    .get_or_insert_with(|| <($($ty,)*) as SystemParam>::init_state(resources));
+                let ($($ty,)*) = <($($ty,)*) as SystemParam>::param(state, resources);
+                fn call_inner<Out, $($ty,)*>(mut f: impl FnMut($($ty,)*) -> Out, $($ty: $ty,)*) -> Out {
+                    f($($ty,)*)
                 }
-                call_inner(&mut self.f, $($ty::param(_resources),)*)
+                call_inner(&mut self.f, $($ty,)*)
+            }
+
+            fn name(&self) -> &'static str {
+                std::any::type_name::<F>()
+            }
+
+            fn access(&self) -> SystemAccess {
+                let mut _access = SystemAccess::default();
+                $($ty::access(&mut _access);)*
+                _access
+            }
+        }
+
+        #[allow(
+            non_snake_case,
+            reason = "Certain variable names are provided by the caller, not by us."
+        )]
+        impl<F: FnMut($($ty,)*) -> Out, Out, $($ty,)*> System for FunctionSystem<F, Out, ($($ty,)*)>
+        where 
+            Out: IntoResult,
+            $($ty : SystemParam,)*
+            for<'a, 'b> &'a mut F: FnMut($($ty,)*) -> Out + FnMut($(<$ty as SystemParam>::Item<'b>,)*) -> Out { 
+            fn run(&mut self, resources: &mut Resources) {
+                if let Err(e) = self.run_io((), resources).into_result() {
+                    report_system_error(resources, IoSystem::name(self), e);
+                }
+            }
+
+            fn name(&self) -> &'static str {
+                IoSystem::name(self)
+            }
+
+            fn access(&self) -> SystemAccess {
+                IoSystem::access(self)
             }
         }
 
@@ -69,13 +100,28 @@ macro_rules! impl_into_system {
             non_snake_case,
             reason = "Certain variable names are provided by the caller, not by us."
         )]
-        impl<F: FnMut($($ty,)*), $($ty,)*> IntoSystem<($($ty,)*)> for F
+        impl<F: FnMut($($ty,)*) -> Out, Out, $($ty,)*> IntoSystem<(Out, ($($ty,)*))> for F
         where 
+            Out: IntoResult,
             $($ty : SystemParam,)*
-            for<'a, 'b> &'a mut F: FnMut($($ty,)*) + FnMut($(<$ty as SystemParam>::Item<'b>,)*) { 
-            type System = FunctionSystem<F, ($($ty),*)>;
+            for<'a, 'b> &'a mut F: FnMut($($ty,)*) -> Out + FnMut($(<$ty as SystemParam>::Item<'b>,)*) -> Out { 
+            type System = FunctionSystem<F, Out, ($($ty,)*)>;
             fn into_system(self) -> Self::System {
-                FunctionSystem{ f: self, marker: PhantomData }
+                FunctionSystem::new(self)
+            }
+        }
+
+        #[allow(
+            non_snake_case,
+            reason = "Certain variable names are provided by the caller, not by us."
+        )]
+        impl<F: FnMut($($ty,)*) -> Out, Out, $($ty,)*> IntoIoSystem<(Out, ($($ty,)*))> for F
+        where 
+            $($ty : SystemParam,)*
+            for<'a, 'b> &'a mut F: FnMut($($ty,)*) -> Out + FnMut($(<$ty as SystemParam>::Item<'b>,)*) -> Out { 
+            type System = FunctionSystem<F, Out, ($($ty,)*)>;
+            fn into_io_system(self) -> Self::System {
+                FunctionSystem::new(self)
             }
         }
 
@@ -87,4 +133,4 @@ impl_into_system!(In1);
 impl_into_system!(In1, In2);
 impl_into_system!(In1, In2, In3);
 impl_into_system!(In1, In2, In3, In4);
-impl_into_system!(In1, In2, In3, In4, In5);
\ No newline at end of file
+impl_into_system!(In1, In2, In3, In4, In5);
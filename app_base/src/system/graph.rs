@@ -0,0 +1,208 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Write as _,
+    path::Path,
+};
+
+use crate::system::{
+    SystemAccess,
+    scheduler::{ScheduledSystem, Scheduler},
+};
+
+struct ResourceNodes {
+    ids: BTreeMap<&'static str, usize>,
+}
+
+impl ResourceNodes {
+    fn id(&mut self, name: &'static str) -> usize {
+        let next = self.ids.len();
+        *self.ids.entry(name).or_insert(next)
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn node_label(name: &str, plugin: Option<&str>) -> String {
+    match plugin {
+        Some(plugin) => format!("{}\\n[{}]", escape(name), escape(plugin)),
+        None => escape(name),
+    }
+}
+
+fn write_access(dot: &mut String, node: &str, access: &SystemAccess, resources: &mut ResourceNodes) {
+    for read in &access.reads {
+        let _ = writeln!(dot, "  r{} -> {} [style=dashed, color=gray40];", resources.id(read), node);
+    }
+    for write in &access.writes {
+        let _ = writeln!(dot, "  {} -> r{} [color=firebrick];", node, resources.id(write));
+    }
+}
+
+#[derive(Default)]
+struct ResourceUse {
+    writer: Option<usize>,
+    readers: Vec<usize>,
+}
+
+// a system depends on the last earlier writer of everything it touches and,
+// when it writes, on the readers since then; exclusive systems touch everything
+fn dependency_edges(systems: &[ScheduledSystem]) -> BTreeSet<(usize, usize)> {
+    let mut edges = BTreeSet::new();
+    let mut uses: HashMap<&'static str, ResourceUse> = HashMap::new();
+    let mut exclusive: Option<usize> = None;
+    let mut since_exclusive: Vec<usize> = vec![];
+    for (index, scheduled) in systems.iter().enumerate() {
+        if scheduled.system.is_exclusive() {
+            if since_exclusive.is_empty() {
+                edges.extend(exclusive.map(|earlier| (earlier, index)));
+            }
+            // the others already come before one of these
+            let leaves: Vec<_> = since_exclusive
+                .drain(..)
+                .filter(|earlier| !edges.iter().any(|(from, _)| from == earlier))
+                .collect();
+            edges.extend(leaves.into_iter().map(|earlier| (earlier, index)));
+            exclusive = Some(index);
+            uses.clear();
+            continue;
+        }
+        let access = scheduled.system.access();
+        let mut depends: BTreeSet<usize> = exclusive.into_iter().collect();
+        for read in &access.reads {
+            let entry = uses.entry(*read).or_default();
+            depends.extend(entry.writer);
+            entry.readers.push(index);
+        }
+        for write in &access.writes {
+            let entry = uses.entry(*write).or_default();
+            depends.extend(entry.writer);
+            depends.extend(entry.readers.drain(..).filter(|reader| *reader != index));
+            entry.writer = Some(index);
+        }
+        edges.extend(depends.into_iter().map(|earlier| (earlier, index)));
+        since_exclusive.push(index);
+    }
+    edges
+}
+
+/// Renders every schedule label, its systems in execution order, their resource
+/// access and the window event systems as a Graphviz DOT graph.
+pub fn schedule_graph(scheduler: &Scheduler) -> String {
+    let mut dot = String::from("digraph schedule {\n  rankdir=LR;\n  compound=true;\n  node [shape=box, style=rounded];\n");
+    let mut resources = ResourceNodes {
+        ids: BTreeMap::new(),
+    };
+
+    let mut labels: Vec<_> = scheduler.labels().collect();
+    labels.sort_by_key(|(order, _)| *order);
+
+    // last system of the previous frame label, used to chain labels in run order
+    let mut previous: Option<String> = None;
+    for (order, label) in labels {
+        let systems = scheduler.systems(order);
+        let on_demand = scheduler.is_on_demand(order);
+        let _ = writeln!(dot, "  subgraph cluster_{} {{", order);
+        let _ = writeln!(
+            dot,
+            "    label=\"{} ({}{})\";",
+            escape(label),
+            order,
+            if on_demand { ", on demand" } else { "" }
+        );
+        if on_demand {
+            dot.push_str("    style=dashed;\n");
+        }
+        let nodes: Vec<String> = (0..systems.len()).map(|i| format!("s{}_{}", order, i)).collect();
+        for (node, scheduled) in nodes.iter().zip(systems) {
+            let _ = writeln!(
+                dot,
+                "    {} [label=\"{}\"];",
+                node,
+                node_label(scheduled.system.name(), scheduled.plugin)
+            );
+        }
+        dot.push_str("  }\n");
+
+        for (from, to) in dependency_edges(systems) {
+            let _ = writeln!(dot, "  {} -> {} [style=bold];", nodes[from], nodes[to]);
+        }
+        for (node, scheduled) in nodes.iter().zip(systems) {
+            write_access(&mut dot, node, &scheduled.system.access(), &mut resources);
+        }
+
+        if on_demand || nodes.is_empty() {
+            continue;
+        }
+        if let Some(previous) = &previous {
+            let _ = writeln!(dot, "  {} -> {} [style=bold, color=blue];", previous, nodes[0]);
+        }
+        previous = nodes.last().cloned();
+    }
+
+    let registered: Vec<_> = scheduler.registered_systems().collect();
+    if !registered.is_empty() {
+        dot.push_str("  subgraph cluster_registered {\n    label=\"Registered systems\";\n    style=dashed;\n");
+        for (i, (id, scheduled)) in registered.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    o{} [label=\"{}\\n{:?}\"];",
+                i,
+                node_label(scheduled.system.name(), scheduled.plugin),
+                id
+            );
+        }
+        dot.push_str("  }\n");
+        for (i, (_, scheduled)) in registered.iter().enumerate() {
+            write_access(&mut dot, &format!("o{}", i), &scheduled.system.access(), &mut resources);
+        }
+    }
+
+    let observers: Vec<_> = scheduler.observers().collect();
+    if !observers.is_empty() {
+        dot.push_str("  subgraph cluster_observers {\n    label=\"Observers\";\n    style=dashed;\n");
+        for (i, scheduled) in observers.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    b{} [label=\"{}\"];",
+                i,
+                node_label(scheduled.system.name(), scheduled.plugin)
+            );
+        }
+        dot.push_str("  }\n");
+        for (i, scheduled) in observers.iter().enumerate() {
+            write_access(&mut dot, &format!("b{}", i), &scheduled.system.access(), &mut resources);
+        }
+    }
+
+    let window_systems = scheduler.window_event_systems();
+    if !window_systems.is_empty() {
+        dot.push_str("  subgraph cluster_window_events {\n    label=\"Window events\";\n");
+        for (i, scheduled) in window_systems.iter().enumerate() {
+            let _ = writeln!(
+                dot,
+                "    w{} [label=\"{}\"];",
+                i,
+                node_label(scheduled.system.name(), scheduled.plugin)
+            );
+        }
+        dot.push_str("  }\n");
+        for i in 1..window_systems.len() {
+            let _ = writeln!(dot, "  w{} -> w{} [style=bold];", i - 1, i);
+        }
+        for (i, scheduled) in window_systems.iter().enumerate() {
+            write_access(&mut dot, &format!("w{}", i), &scheduled.system.access(), &mut resources);
+        }
+    }
+
+    for (name, id) in &resources.ids {
+        let _ = writeln!(dot, "  r{} [label=\"{}\", shape=ellipse, style=filled, fillcolor=lightyellow];", id, escape(name));
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+pub fn write_schedule_graph<P: AsRef<Path>>(scheduler: &Scheduler, path: P) -> std::io::Result<()> {
+    std::fs::write(path, schedule_graph(scheduler))
+}
@@ -0,0 +1,172 @@
+use std::marker::PhantomData;
+
+use crate::system::{observer::TriggeredEvent, scheduler::Scheduler};
+
+pub type ResourceHook = Box<dyn FnMut(&mut Scheduler)>;
+
+pub struct ScheduledHook {
+    pub hook: ResourceHook,
+    pub plugin: Option<&'static str>,
+}
+
+/// Callbacks for one resource type, they run right away while the scheduler
+/// inserts or removes the resource, unlike observers which run after the flush.
+#[derive(Default)]
+pub struct ResourceHooks {
+    insert: Vec<ScheduledHook>,
+    replace: Vec<ScheduledHook>,
+    remove: Vec<ScheduledHook>,
+    // the plugin being built when these hooks were handed out
+    plugin: Option<&'static str>,
+    // known once the type was seen with its static type, type erased inserts
+    // and removals use it to trigger the typed lifecycle events
+    events: Option<LifecycleEvents>,
+    // the plugin whose copy of the event constructors `events` points into
+    events_plugin: Option<&'static str>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct LifecycleEvents {
+    added: fn() -> TriggeredEvent,
+    removed: fn() -> TriggeredEvent,
+}
+
+fn triggered<E: Default + 'static>() -> TriggeredEvent {
+    TriggeredEvent {
+        event: Box::new(E::default()),
+        name: std::any::type_name::<E>(),
+    }
+}
+
+impl LifecycleEvents {
+    pub(crate) fn of<T: 'static>() -> Self {
+        LifecycleEvents {
+            added: triggered::<ResourceAdded<T>>,
+            removed: triggered::<ResourceRemoved<T>>,
+        }
+    }
+
+    pub(crate) fn added(&self) -> TriggeredEvent {
+        (self.added)()
+    }
+
+    pub(crate) fn removed(&self) -> TriggeredEvent {
+        (self.removed)()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookKind {
+    /// After a resource was inserted or replaced.
+    Insert,
+    /// Before an existing resource is overwritten, the old value is still there.
+    Replace,
+    /// Before the resource is removed, it is still there.
+    Remove,
+}
+
+impl ResourceHooks {
+    pub fn on_insert<F: FnMut(&mut Scheduler) + 'static>(&mut self, hook: F) -> &mut Self {
+        self.insert.push(ScheduledHook {
+            hook: Box::new(hook),
+            plugin: self.plugin,
+        });
+        self
+    }
+
+    pub fn on_replace<F: FnMut(&mut Scheduler) + 'static>(&mut self, hook: F) -> &mut Self {
+        self.replace.push(ScheduledHook {
+            hook: Box::new(hook),
+            plugin: self.plugin,
+        });
+        self
+    }
+
+    pub fn on_remove<F: FnMut(&mut Scheduler) + 'static>(&mut self, hook: F) -> &mut Self {
+        self.remove.push(ScheduledHook {
+            hook: Box::new(hook),
+            plugin: self.plugin,
+        });
+        self
+    }
+
+    pub(crate) fn set_plugin(&mut self, plugin: Option<&'static str>) {
+        self.plugin = plugin;
+    }
+
+    pub(crate) fn remove_plugin(&mut self, plugin: &str) {
+        for hooks in [&mut self.insert, &mut self.replace, &mut self.remove] {
+            hooks.retain(|hook| hook.plugin != Some(plugin));
+        }
+        if self.events_plugin == Some(plugin) {
+            self.events = None;
+            self.events_plugin = None;
+        }
+    }
+
+    // the host's constructors win over a plugin's, they stay valid for good
+    pub(crate) fn set_events(&mut self, events: LifecycleEvents, plugin: Option<&'static str>) {
+        if self.events.is_none() || (self.events_plugin.is_some() && plugin.is_none()) {
+            self.events = Some(events);
+            self.events_plugin = plugin;
+        }
+    }
+
+    pub(crate) fn events(&self) -> Option<LifecycleEvents> {
+        self.events
+    }
+
+    pub fn hooks_mut(&mut self, kind: HookKind) -> &mut Vec<ScheduledHook> {
+        match kind {
+            HookKind::Insert => &mut self.insert,
+            HookKind::Replace => &mut self.replace,
+            HookKind::Remove => &mut self.remove,
+        }
+    }
+}
+
+/// Triggered when a resource of type `T` is inserted where there was none.
+pub struct ResourceAdded<T> {
+    marker: PhantomData<fn() -> T>,
+}
+
+/// Triggered after the resource of type `T` was removed.
+pub struct ResourceRemoved<T> {
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ResourceAdded<T> {
+    pub fn new() -> Self {
+        ResourceAdded { marker: PhantomData }
+    }
+}
+
+impl<T> ResourceRemoved<T> {
+    pub fn new() -> Self {
+        ResourceRemoved { marker: PhantomData }
+    }
+}
+
+impl<T> Default for ResourceAdded<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Default for ResourceRemoved<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for ResourceAdded<T> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for ResourceRemoved<T> {
+    fn clone(&self) -> Self {
+        Self::new()
+    }
+}
@@ -1,27 +1,96 @@
 use std::{
     any::{Any, TypeId},
     cell::RefCell,
+    fmt::Display,
+    hash::Hash,
+    sync::Arc,
 };
 
 use log::info;
-use winit::event_loop::ActiveEventLoop;
 
 use crate::{
-    ApplicationEvent, ChannelResource, EventReader, EventWriter,
-    storage::{Res, ResMut, ResOwned, Resource, Resources},
+    ApplicationEvent, context::EventLoopContext, events::channel::{EventChannel, EventConfig}, ChannelResource, EventReader, EventWriter,
+    storage::{FromResources, KeyedIndex, Res, ResInit, ResKeyed, ResKeyedMut, ResMut, ResMutInit, ResOwned, Resource, ResourceIndex, Resources},
     system::scheduler::{Scheduler, StoredSystem},
 };
 
+pub mod async_system;
 pub mod commands;
+pub mod exclusive_system;
 pub mod function_system;
+pub mod graph;
+pub mod hooks;
+pub mod observer;
+pub mod pipe;
 pub mod scheduler;
+pub mod undo;
 pub mod window_event_system;
 
+/// `init_state` runs once, the first time a system runs, and resolves whatever
+/// the parameter needs (resource indices, channel subscriptions) so `param`
+/// can fetch it cheaply every frame. `prepare` still runs before every `param`.
 pub trait SystemParam {
     type Item<'new>;
+    type State: 'static;
 
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r>;
+    fn init_state(resources: &mut Resources) -> Self::State;
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r>;
     fn prepare<'r>(_resources: &'r mut Resources) {}
+    fn access(_access: &mut SystemAccess) {}
+}
+
+macro_rules! impl_system_param_tuple {
+    ($($ty:ident),*) => {
+        #[allow(
+            non_snake_case,
+            reason = "Certain variable names are provided by the caller, not by us."
+        )]
+        #[allow(clippy::unused_unit, reason = "The empty tuple expands to `()`.")]
+        impl<$($ty: SystemParam,)*> SystemParam for ($($ty,)*) {
+            type Item<'new> = ($($ty::Item<'new>,)*);
+            type State = ($($ty::State,)*);
+
+            fn init_state(_resources: &mut Resources) -> Self::State {
+                ($($ty::init_state(_resources),)*)
+            }
+
+            fn param<'r>(state: &'r mut Self::State, _resources: &'r Resources) -> Self::Item<'r> {
+                let ($($ty,)*) = state;
+                ($($ty::param($ty, _resources),)*)
+            }
+
+            fn prepare<'r>(_resources: &'r mut Resources) {
+                $($ty::prepare(_resources);)*
+            }
+
+            fn access(_access: &mut SystemAccess) {
+                $($ty::access(_access);)*
+            }
+        }
+    };
+}
+
+impl_system_param_tuple!();
+impl_system_param_tuple!(In1);
+impl_system_param_tuple!(In1, In2);
+impl_system_param_tuple!(In1, In2, In3);
+impl_system_param_tuple!(In1, In2, In3, In4);
+impl_system_param_tuple!(In1, In2, In3, In4, In5);
+
+#[derive(Debug, Clone, Default)]
+pub struct SystemAccess {
+    pub reads: Vec<&'static str>,
+    pub writes: Vec<&'static str>,
+}
+
+impl SystemAccess {
+    pub fn read<T: ?Sized>(&mut self) {
+        self.reads.push(std::any::type_name::<T>());
+    }
+
+    pub fn write<T: ?Sized>(&mut self) {
+        self.writes.push(std::any::type_name::<T>());
+    }
 }
 
 pub struct StoredSystemsContainer {
@@ -132,102 +201,295 @@ pub trait WindowEventSystem {
     fn run(
         &mut self,
         window_event: ApplicationEvent,
-        event_loop: &ActiveEventLoop,
+        event_loop: &dyn EventLoopContext,
         resources: &mut Resources,
     );
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+    fn access(&self) -> SystemAccess {
+        SystemAccess::default()
+    }
 }
 
 pub trait System {
     fn run(&mut self, resources: &mut Resources);
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+    fn access(&self) -> SystemAccess {
+        SystemAccess::default()
+    }
+    fn is_exclusive(&self) -> bool {
+        false
+    }
+    fn run_exclusive(&mut self, scheduler: &mut Scheduler) {
+        self.run(scheduler.resources_mut());
+    }
+}
+
+pub trait IntoResult {
+    fn into_result(self) -> Result<(), String>;
+}
+
+impl IntoResult for () {
+    fn into_result(self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl<E: Display> IntoResult for Result<(), E> {
+    fn into_result(self) -> Result<(), String> {
+        self.map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SystemError {
+    pub system: &'static str,
+    pub message: String,
+}
+
+impl Display for SystemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "system {} failed: {}", self.system, self.message)
+    }
+}
+
+pub struct SystemErrorHandler(pub Box<dyn FnMut(&SystemError)>);
+
+impl SystemErrorHandler {
+    pub fn new<F: FnMut(&SystemError) + 'static>(handler: F) -> Self {
+        SystemErrorHandler(Box::new(handler))
+    }
+
+    pub fn log() -> Self {
+        Self::new(|error| log::error!("{}", error))
+    }
+}
+
+pub fn report_system_error(resources: &Resources, system: &'static str, message: String) {
+    let error = SystemError { system, message };
+    match resources.get_mut::<SystemErrorHandler>() {
+        Some(mut handler) => (handler.0)(&error),
+        None => log::error!("{}", error),
+    }
+}
+
+fn missing_resource<T>() -> ! {
+    panic!("resource {} does not exist", std::any::type_name::<T>())
 }
 
 impl<T: Resource + 'static> SystemParam for Option<Res<'_, T>> {
     type Item<'new> = Option<Res<'new, T>>;
+    type State = Option<ResourceIndex>;
+
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.index_of::<T>()
+    }
 
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-        resources.get::<T>()
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        let index = resources.resolve_index::<T>(state)?;
+        resources.get_indexed::<T>(index)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.read::<T>();
     }
 }
 
 impl<T: Resource + 'static> SystemParam for Option<ResMut<'_, T>> {
     type Item<'new> = Option<ResMut<'new, T>>;
+    type State = Option<ResourceIndex>;
+
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.index_of::<T>()
+    }
+
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        let index = resources.resolve_index::<T>(state)?;
+        resources.get_indexed_mut::<T>(index)
+    }
 
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-        resources.get_mut::<T>()
+    fn access(access: &mut SystemAccess) {
+        access.write::<T>();
     }
 }
 
 impl<T: Resource + Clone + 'static> SystemParam for Option<ResOwned<T>> {
     type Item<'new> = Option<ResOwned<T>>;
+    type State = Option<ResourceIndex>;
+
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.index_of::<T>()
+    }
+
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        let index = resources.resolve_index::<T>(state)?;
+        resources.get_indexed::<T>(index).map(|val| ResOwned::new(val.clone()))
+    }
 
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-        resources.get::<T>().map(|val| ResOwned::new(val.clone()))
+    fn access(access: &mut SystemAccess) {
+        access.read::<T>();
     }
 }
 
 impl<T: Resource + 'static> SystemParam for Res<'_, T> {
     type Item<'new> = Res<'new, T>;
+    type State = Option<ResourceIndex>;
 
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-        Option::<Res<'r, T>>::param(resources).unwrap()
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.index_of::<T>()
+    }
+
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        Option::<Res<'r, T>>::param(state, resources).unwrap_or_else(|| missing_resource::<T>())
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.read::<T>();
     }
 }
 
 impl<T: Resource + 'static> SystemParam for ResMut<'_, T> {
     type Item<'new> = ResMut<'new, T>;
+    type State = Option<ResourceIndex>;
+
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.index_of::<T>()
+    }
+
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        Option::<ResMut<'r, T>>::param(state, resources).unwrap_or_else(|| missing_resource::<T>())
+    }
 
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-        Option::<ResMut<'r, T>>::param(resources).unwrap()
+    fn access(access: &mut SystemAccess) {
+        access.write::<T>();
     }
 }
 impl<T: Resource + Clone + 'static> SystemParam for ResOwned<T> {
     type Item<'new> = ResOwned<T>;
+    type State = Option<ResourceIndex>;
 
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-        Option::<ResOwned<T>>::param(resources).unwrap()
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.index_of::<T>()
+    }
+
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        Option::<ResOwned<T>>::param(state, resources).unwrap_or_else(|| missing_resource::<T>())
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.read::<T>();
     }
 }
 
-// impl<T1: SystemParam, T2: SystemParam> SystemParam for (T1, T2) {
-//     type Item<'new> = (T1::Item<'new>, T2::Item<'new>) ;
+impl<T: Resource + FromResources + 'static> SystemParam for ResInit<'_, T> {
+    type Item<'new> = ResInit<'new, T>;
+    type State = ResourceIndex;
 
-//     fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-//         (
-//             T1::param(resources),
-//             T2::param(resources),
-//         )
-//     }
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.init::<T>()
+    }
 
-// }
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        ResInit::new(resources.get_indexed(*state).unwrap_or_else(|| missing_resource::<T>()))
+    }
 
+    fn access(access: &mut SystemAccess) {
+        access.read::<T>();
+    }
+}
+
+impl<T: Resource + FromResources + 'static> SystemParam for ResMutInit<'_, T> {
+    type Item<'new> = ResMutInit<'new, T>;
+    type State = ResourceIndex;
+
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.init::<T>()
+    }
+
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        ResMutInit::new(resources.get_indexed_mut(*state).unwrap_or_else(|| missing_resource::<T>()))
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.write::<T>();
+    }
+}
+
+impl<K: Hash + Eq + 'static, T: Resource + 'static> SystemParam for ResKeyed<'_, K, T> {
+    type Item<'new> = ResKeyed<'new, K, T>;
+    type State = KeyedIndex;
+
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.keyed_index::<K, T>()
+    }
+
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        resources.keyed_indexed::<K, T>(*state)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.read::<ResKeyed<'static, K, T>>();
+    }
+}
+
+impl<K: Hash + Eq + 'static, T: Resource + 'static> SystemParam for ResKeyedMut<'_, K, T> {
+    type Item<'new> = ResKeyedMut<'new, K, T>;
+    type State = KeyedIndex;
+
+    fn init_state(resources: &mut Resources) -> Self::State {
+        resources.keyed_index::<K, T>()
+    }
+
+    fn param<'r>(state: &'r mut Self::State, resources: &'r Resources) -> Self::Item<'r> {
+        resources.keyed_indexed_mut::<K, T>(*state)
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.write::<ResKeyed<'static, K, T>>();
+    }
+}
+
+// readers and writers of other plugins hold on to the channel, it is not removed with its creator
+fn event_channel<T: Clone + 'static>(resources: &mut Resources) -> Arc<EventChannel<T>> {
+    resources.share::<ChannelResource<T>>();
+    resources.add_if_not_present(ChannelResource::<T>::new(EventConfig::default()));
+    resources.get::<ChannelResource<T>>().unwrap().channel().clone()
+}
+
+// the reader subscribes once, so events sent between two runs of the system are not missed
 impl<T: Clone + 'static> SystemParam for EventReader<T> {
     type Item<'new> = EventReader<T>;
+    type State = EventReader<T>;
 
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-        let resource = resources.get::<ChannelResource<T>>().unwrap();
+    fn init_state(resources: &mut Resources) -> Self::State {
+        EventReader::new(event_channel::<T>(resources))
+    }
 
-        return EventReader::new(resource.sender.clone());
+    fn param<'r>(state: &'r mut Self::State, _resources: &'r Resources) -> Self::Item<'r> {
+        state.share()
     }
 
-    fn prepare<'r>(resources: &'r mut Resources) {
-        let res = resources.get::<ChannelResource<T>>().is_none();
-        if res {
-            resources.add(ChannelResource::<T>::new(100));
-        }
+    fn access(access: &mut SystemAccess) {
+        access.read::<ChannelResource<T>>();
     }
 }
 
 impl<T: Clone + 'static> SystemParam for EventWriter<T> {
     type Item<'new> = EventWriter<T>;
-    fn param<'r>(resources: &'r Resources) -> Self::Item<'r> {
-        let resource = resources.get::<ChannelResource<T>>().unwrap();
-        return EventWriter::new(resource.sender.clone());
+    type State = EventWriter<T>;
+
+    fn init_state(resources: &mut Resources) -> Self::State {
+        EventWriter::new(event_channel::<T>(resources))
     }
 
-    fn prepare<'r>(resources: &'r mut Resources) {
-        let res = resources.get::<ChannelResource<T>>().is_none();
-        if res {
-            resources.add(ChannelResource::<T>::new(100));
-        }
+    fn param<'r>(state: &'r mut Self::State, _resources: &'r Resources) -> Self::Item<'r> {
+        state.clone()
+    }
+
+    fn access(access: &mut SystemAccess) {
+        access.write::<ChannelResource<T>>();
     }
 }
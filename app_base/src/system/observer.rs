@@ -0,0 +1,50 @@
+use std::any::Any;
+
+use crate::{
+    storage::Resources,
+    system::{IntoResult, SystemAccess, pipe::IoSystem, report_system_error},
+};
+
+pub trait ObserverSystem {
+    fn run(&mut self, event: &dyn Any, resources: &mut Resources);
+    fn name(&self) -> &'static str;
+    fn access(&self) -> SystemAccess;
+}
+
+pub struct Observer<S> {
+    system: S,
+}
+
+impl<S> Observer<S> {
+    pub fn new(system: S) -> Self {
+        Observer { system }
+    }
+}
+
+impl<S: IoSystem> ObserverSystem for Observer<S>
+where
+    S::In: Clone + 'static,
+    S::Out: IntoResult,
+{
+    fn run(&mut self, event: &dyn Any, resources: &mut Resources) {
+        let Some(event) = event.downcast_ref::<S::In>() else {
+            return;
+        };
+        if let Err(e) = self.system.run_io(event.clone(), resources).into_result() {
+            report_system_error(resources, self.system.name(), e);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        self.system.name()
+    }
+
+    fn access(&self) -> SystemAccess {
+        self.system.access()
+    }
+}
+
+pub struct TriggeredEvent {
+    pub event: Box<dyn Any>,
+    pub name: &'static str,
+}
@@ -0,0 +1,191 @@
+use std::marker::PhantomData;
+
+use crate::{
+    storage::Resources,
+    system::{IntoResult, IntoSystem, System, SystemAccess, SystemParam, report_system_error, scheduler::Scheduler},
+};
+
+/// Output of the previous system in a pipe, always the first parameter of the receiving system.
+pub struct In<T>(pub T);
+
+pub trait IoSystem {
+    type In;
+    type Out;
+    fn run_io(&mut self, input: Self::In, resources: &mut Resources) -> Self::Out;
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+    fn access(&self) -> SystemAccess {
+        SystemAccess::default()
+    }
+    fn is_exclusive(&self) -> bool {
+        false
+    }
+    fn run_io_exclusive(&mut self, input: Self::In, scheduler: &mut Scheduler) -> Self::Out {
+        self.run_io(input, scheduler.resources_mut())
+    }
+}
+
+pub trait IntoIoSystem<Input>: Sized {
+    type System: IoSystem;
+    fn into_io_system(self) -> Self::System;
+
+    fn pipe<B: IntoIoSystem<BInput>, BInput>(self, other: B) -> PipeSystem<Self::System, B::System>
+    where
+        B::System: IoSystem<In = <Self::System as IoSystem>::Out>,
+    {
+        PipeSystem {
+            first: self.into_io_system(),
+            second: other.into_io_system(),
+        }
+    }
+}
+
+pub struct PipeSystem<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: IoSystem, B: IoSystem<In = A::Out>> IoSystem for PipeSystem<A, B> {
+    type In = A::In;
+    type Out = B::Out;
+
+    fn run_io(&mut self, input: A::In, resources: &mut Resources) -> B::Out {
+        let output = self.first.run_io(input, resources);
+        self.second.run_io(output, resources)
+    }
+
+    fn name(&self) -> &'static str {
+        self.second.name()
+    }
+
+    fn access(&self) -> SystemAccess {
+        let mut access = self.first.access();
+        let second = self.second.access();
+        access.reads.extend(second.reads);
+        access.writes.extend(second.writes);
+        access
+    }
+
+    fn is_exclusive(&self) -> bool {
+        self.first.is_exclusive() || self.second.is_exclusive()
+    }
+
+    fn run_io_exclusive(&mut self, input: A::In, scheduler: &mut Scheduler) -> B::Out {
+        let output = self.first.run_io_exclusive(input, scheduler);
+        self.second.run_io_exclusive(output, scheduler)
+    }
+}
+
+impl<A: IoSystem<In = ()>, B: IoSystem<In = A::Out>> System for PipeSystem<A, B>
+where
+    B::Out: IntoResult,
+{
+    fn run(&mut self, resources: &mut Resources) {
+        if let Err(e) = self.run_io((), resources).into_result() {
+            report_system_error(resources, IoSystem::name(self), e);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        IoSystem::name(self)
+    }
+
+    fn access(&self) -> SystemAccess {
+        IoSystem::access(self)
+    }
+
+    fn is_exclusive(&self) -> bool {
+        IoSystem::is_exclusive(self)
+    }
+
+    fn run_exclusive(&mut self, scheduler: &mut Scheduler) {
+        if let Err(e) = self.run_io_exclusive((), scheduler).into_result() {
+            report_system_error(scheduler.resources(), IoSystem::name(self), e);
+        }
+    }
+}
+
+pub struct PipeMarker;
+
+impl<A: IoSystem<In = ()>, B: IoSystem<In = A::Out>> IntoSystem<PipeMarker> for PipeSystem<A, B>
+where
+    B::Out: IntoResult,
+{
+    type System = Self;
+    fn into_system(self) -> Self::System {
+        self
+    }
+}
+
+impl<A: IoSystem, B: IoSystem<In = A::Out>> IntoIoSystem<PipeMarker> for PipeSystem<A, B> {
+    type System = Self;
+    fn into_io_system(self) -> Self::System {
+        self
+    }
+}
+
+pub struct InputFunctionSystem<F, T, Out, P: SystemParam> {
+    f: F,
+    state: Option<P::State>,
+    marker: PhantomData<fn(T) -> (Out, P)>,
+}
+
+macro_rules! impl_input_system_function {
+
+    ($($ty:ident),*) => {
+          #[allow(
+            non_snake_case,
+            reason = "Certain variable names are provided by the caller, not by us."
+        )]
+        impl<F: FnMut(In<T>, $($ty,)*) -> Out, T, Out, $($ty,)*> IoSystem for InputFunctionSystem<F, T, Out, ($($ty,)*)>
+        where
+            $($ty : SystemParam,)*
+            for<'a, 'b> &'a mut F: FnMut(In<T>, $($ty,)*) -> Out + FnMut(In<T>, $(<$ty as SystemParam>::Item<'b>,)*) -> Out {
+            type In = T;
+            type Out = Out;
+
+            fn run_io(&mut self, input: T, resources: &mut Resources) -> Out {
+                <($($ty,)*) as SystemParam>::prepare(resources);
+                let state = self
+                    .state
+                    .get_or_insert_with(|| <($($ty,)*) as SystemParam>::init_state(resources));
+                let ($($ty,)*) = <($($ty,)*) as SystemParam>::param(state, resources);
+                fn call_inner<T, Out, $($ty,)*>(mut f: impl FnMut(In<T>, $($ty,)*) -> Out, input: In<T>, $($ty: $ty,)*) -> Out {
+                    f(input, $($ty,)*)
+                }
+                call_inner(&mut self.f, In(input), $($ty,)*)
+            }
+
+            fn name(&self) -> &'static str {
+                std::any::type_name::<F>()
+            }
+
+            fn access(&self) -> SystemAccess {
+                let mut _access = SystemAccess::default();
+                $($ty::access(&mut _access);)*
+                _access
+            }
+        }
+
+        #[allow(
+            non_snake_case,
+            reason = "Certain variable names are provided by the caller, not by us."
+        )]
+        impl<F: FnMut(In<T>, $($ty,)*) -> Out, T, Out, $($ty,)*> IntoIoSystem<(In<T>, Out, ($($ty,)*))> for F
+        where
+            $($ty : SystemParam,)*
+            for<'a, 'b> &'a mut F: FnMut(In<T>, $($ty,)*) -> Out + FnMut(In<T>, $(<$ty as SystemParam>::Item<'b>,)*) -> Out {
+            type System = InputFunctionSystem<F, T, Out, ($($ty,)*)>;
+            fn into_io_system(self) -> Self::System {
+                InputFunctionSystem { f: self, state: None, marker: PhantomData }
+            }
+        }
+    };
+}
+
+impl_input_system_function!();
+impl_input_system_function!(In1);
+impl_input_system_function!(In1, In2);
+impl_input_system_function!(In1, In2, In3);
+impl_input_system_function!(In1, In2, In3, In4);
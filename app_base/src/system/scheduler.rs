@@ -1,25 +1,129 @@
 
-use std::{any::{Any, TypeId}, cell::RefCell, collections::HashMap, usize};
+use std::{any::{Any, TypeId}, cell::RefCell, collections::{HashMap, HashSet}, hash::Hash, time::Instant, usize};
 
-use log::{debug, info};
+use log::{debug, info, warn};
 use uuid::Uuid;
-use winit::event_loop::ActiveEventLoop;
 
-use crate::{storage::{Res, ResMut, Resource, Resources}, system::{ IntoStoredSystems, IntoSystem, IntoWindowEventSystem, System, WindowEventSystem}, ApplicationEvent};
+
+use crate::{context::EventLoopContext, events::Events, rollback::{RollbackBuffer, RollbackRegistry, Snapshot}, time::{Time, Timers}, system::commands::{CommandError, CommandErrorHandler, CommandList, CommandQueue, QueuedCommand}, profiling::{ProfileKind, SystemProfile}, storage::{FromResources, Res, ResMut, Resource, ResourceSet, Resources}, system::{ IntoResult, IntoStoredSystem, IntoStoredSystems, IntoSystem, IntoWindowEventSystem, System, WindowEventSystem, hooks::{HookKind, LifecycleEvents, ResourceHooks}, observer::{Observer, ObserverSystem, TriggeredEvent}, pipe::{IntoIoSystem, IoSystem}, undo::UndoStack, SystemErrorHandler}, ApplicationEvent};
 
 
 pub type StoredSystem = Box<dyn System>;
 pub type StoredWindowEventSystem = Box<dyn WindowEventSystem>;
 
+pub struct ScheduledSystem {
+    pub id: SystemId,
+    pub system: StoredSystem,
+    pub plugin: Option<&'static str>,
+}
+
+type RunningAs = (Option<&'static str>, Option<&'static str>);
+
+// a system runs as its plugin, so whatever it creates belongs to the plugin, and
+// commands it queues are reported with its name when they fail
+fn run_as(resources: &mut Resources, source: Option<&'static str>, plugin: Option<&'static str>) -> RunningAs {
+    let index = resources.init::<CommandList>();
+    let source = resources
+        .get_indexed_mut::<CommandList>(index)
+        .map(|mut list| list.set_source(source))
+        .unwrap_or_default();
+    (source, resources.set_owner(plugin))
+}
+
+fn restore_running(resources: &mut Resources, (source, plugin): RunningAs) {
+    run_as(resources, source, plugin);
+}
+
+impl ScheduledSystem {
+    // the system must not be stored in the scheduler while it runs, exclusive systems borrow all of it
+    fn run(&mut self, scheduler: &mut Scheduler) {
+        let start = Instant::now();
+        let previous = run_as(&mut scheduler.resources, Some(self.system.name()), self.plugin);
+        if self.system.is_exclusive() {
+            self.system.run_exclusive(scheduler);
+        } else {
+            self.system.run(&mut scheduler.resources);
+        }
+        restore_running(&mut scheduler.resources, previous);
+        if let Some(mut profile) = scheduler.resources.get_mut::<SystemProfile>() {
+            profile.record_system(self.id, self.system.name(), self.plugin, start, start.elapsed());
+        }
+    }
+}
+
+pub struct ScheduledWindowEventSystem {
+    pub system: StoredWindowEventSystem,
+    pub plugin: Option<&'static str>,
+}
+
+pub type StoredObserver = Box<dyn ObserverSystem>;
+
+pub struct ScheduledObserver {
+    pub id: SystemId,
+    pub system: StoredObserver,
+    pub plugin: Option<&'static str>,
+}
+
+impl ScheduledObserver {
+    fn run(&mut self, event: &dyn Any, resources: &mut Resources) {
+        let start = Instant::now();
+        let previous = run_as(resources, Some(self.system.name()), self.plugin);
+        self.system.run(event, resources);
+        restore_running(resources, previous);
+        if let Some(mut profile) = resources.get_mut::<SystemProfile>() {
+            profile.record_system(self.id, self.system.name(), self.plugin, start, start.elapsed());
+        }
+    }
+}
+
+// an observer that keeps triggering its own event would otherwise never let the flush finish
+const MAX_TRIGGER_ROUNDS: usize = 64;
+
 pub struct Scheduler {
-    systems: HashMap<usize, Vec<StoredSystem>>,
-    window_event_handler: Vec<StoredWindowEventSystem>,
+    systems: HashMap<usize, Vec<ScheduledSystem>>,
+    labels: HashMap<usize, String>,
+    registered_systems: HashMap<SystemId, ScheduledSystem>,
+    running_systems: HashSet<SystemId>,
+    removed_while_running: HashSet<SystemId>,
+    on_demand: HashSet<usize>,
+    window_event_handler: Vec<ScheduledWindowEventSystem>,
+    observers: HashMap<TypeId, Vec<ScheduledObserver>>,
+    triggered: Vec<TriggeredEvent>,
+    hooks: HashMap<TypeId, ResourceHooks>,
+    // resources taken out by resource_scope, they still count as present
+    scoped: HashSet<TypeId>,
     resources: Resources,
+    started: bool,
+    late_startup: Vec<ScheduledSystem>,
+    command_source: Option<&'static str>,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct SystemId {
+    id: Uuid,
+}
+
+// random rather than counted, a dynamically loaded plugin has its own copy of every static
+impl SystemId {
+    pub fn new() -> Self {
+        SystemId {
+            id: Uuid::new_v4(),
+        }
+    }
+}
+
+impl Default for SystemId {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub trait Label {
     fn label(&self) -> &str;
     fn order(&self) -> usize;
+    fn on_demand(&self) -> bool {
+        false
+    }
 }
 
 pub struct Startup;
@@ -42,7 +146,7 @@ impl Label for Update {
     }
 
     fn order(&self) -> usize {
-        2
+        3
     }
 }
 
@@ -51,7 +155,7 @@ impl Label for PostUpdate {
         "PostUpdate"
     }
     fn order(&self) -> usize {
-        3
+        4
     }
 }  
 
@@ -63,6 +167,20 @@ impl Label for PreUpdate {
         "PreUpdate"
     }
 
+    fn order(&self) -> usize {
+        2
+    }
+}
+
+/// Runs before `PreUpdate`, for systems that have to see or feed the frame's
+/// input before anything else reads it.
+pub struct First;
+
+impl Label for First {
+    fn label(&self) -> &str {
+        "First"
+    }
+
     fn order(&self) -> usize {
         1
     }
@@ -75,60 +193,451 @@ impl Scheduler {
     pub fn new() -> Self {
         Scheduler { 
             systems: HashMap::new(),
+            labels: HashMap::new(),
+            registered_systems: HashMap::new(),
+            running_systems: HashSet::new(),
+            removed_while_running: HashSet::new(),
+            on_demand: HashSet::new(),
             resources: Resources::new(),
             window_event_handler: vec![],
+            observers: HashMap::new(),
+            triggered: vec![],
+            hooks: HashMap::new(),
+            scoped: HashSet::new(),
+            started: false,
+            late_startup: vec![],
+            command_source: None,
+        }
+    }
+
+    /// The plugin whose code is running, everything added meanwhile is tagged with it.
+    pub fn current_plugin(&self) -> Option<&'static str> {
+        self.resources.owner()
+    }
+
+    pub fn set_current_plugin(&mut self, plugin: Option<&'static str>) -> Option<&'static str> {
+        self.resources.set_owner(plugin)
+    }
+
+    fn schedule(&self, system: StoredSystem) -> ScheduledSystem {
+        ScheduledSystem {
+            id: SystemId::new(),
+            system,
+            plugin: self.current_plugin(),
         }
     }
 
     pub fn add_systems<T: IntoStoredSystems<I>, I>(&mut self, label: impl Label, systems: T){
-        let priority= label.order();
-        let stored =  if let Some(s) = self.systems.get_mut(&priority) {
-            s
+        self.add_stored_systems(&label, systems.into_stored_systems());
+    }
+
+    pub fn add_stored_systems(&mut self, label: &dyn Label, systems: impl IntoIterator<Item = StoredSystem>) {
+        if label.on_demand() {
+            self.on_demand.insert(label.order());
+        }
+        let name = self.labels.entry(label.order()).or_insert_with(|| label.label().to_string());
+        if name != label.label() {
+            warn!("{} has order {} which {} already uses, their systems are merged", label.label(), label.order(), name);
+        }
+        let scheduled: Vec<_> = systems.into_iter().map(|s| self.schedule(s)).collect();
+        // startup systems added after the first frame run once on the next one
+        if self.started && label.order() == Startup.order() {
+            self.late_startup.extend(scheduled);
+            return;
+        }
+        let stored = self.systems.entry(label.order()).or_default();
+        stored.extend(scheduled);
+    }
+
+    pub fn register_system<T: IntoSystem<I>, I>(&mut self, system: T) -> SystemId
+    where
+        T::System: 'static,
+    {
+        let id = SystemId::new();
+        self.insert_system(id, system.into_system().into_stored_system());
+        id
+    }
+
+    pub fn insert_system(&mut self, id: SystemId, system: StoredSystem) {
+        let mut scheduled = self.schedule(system);
+        scheduled.id = id;
+        self.registered_systems.insert(id, scheduled);
+    }
+
+    pub fn remove_system(&mut self, id: SystemId) -> Option<StoredSystem> {
+        if self.running_systems.contains(&id) {
+            self.removed_while_running.insert(id);
+        }
+        self.registered_systems.remove(&id).map(|s| s.system)
+    }
+
+    pub fn run_system(&mut self, id: SystemId) -> bool {
+        // taken out while running so the system may queue itself again
+        if let Some(mut system) = self.registered_systems.remove(&id) {
+            self.running_systems.insert(id);
+            system.run(self);
+            self.running_systems.remove(&id);
+            // a system unregistered during its own run stays gone, one
+            // registered under the same id meanwhile replaces it
+            if !self.removed_while_running.remove(&id) {
+                self.registered_systems.entry(id).or_insert(system);
+            }
+            true
         } else {
-            self.systems.insert(priority, Vec::new());
-            self.systems.get_mut(&priority).unwrap()
-        };
-        for s in systems.into_stored_systems() {
-            stored.push(s);
+            warn!("no system registered for {:?}", id);
+            false
         }
     }
 
 
     pub fn add_window_event_system<T: IntoWindowEventSystem< I> + 'static, I: 'static>(&mut self, system: T)
    {
-        self.window_event_handler.push(Box::new(system.into_system()));
+        self.window_event_handler.push(ScheduledWindowEventSystem {
+            system: Box::new(system.into_system()),
+            plugin: self.current_plugin(),
+        });
     }
 
+    /// Type erased insert with the same hooks as `add_resource`. `ResourceAdded`
+    /// is only triggered for types the scheduler has already seen typed.
     pub fn insert_entity(&mut self, entity: (TypeId, RefCell<Box<dyn Any>>)) {
-        self.resources.add_entry(entity)
+        let (id, value) = entity;
+        self.insert_with(id, |resources| resources.add_entry((id, value)));
+    }
+
+    /// Type erased counterpart of `remove_resource`.
+    pub fn remove_entity(&mut self, id: TypeId) -> Option<Box<dyn Any>> {
+        if !self.resources.contains_id(id) {
+            return None;
+        }
+        self.run_hooks(id, HookKind::Remove);
+        let removed = self.resources.remove_entry(id);
+        if removed.is_some() {
+            self.trigger_lifecycle(id, LifecycleEvents::removed);
+        }
+        removed
+    }
+
+    // every insert goes through here: Replace hooks while the old value is still
+    // there, the write, then Insert hooks and ResourceAdded if there was none
+    fn insert_with(&mut self, id: TypeId, write: impl FnOnce(&mut Resources)) {
+        let replaced = self.resources.contains_id(id) || self.scoped.contains(&id);
+        if replaced {
+            self.run_hooks(id, HookKind::Replace);
+        }
+        write(&mut self.resources);
+        self.run_hooks(id, HookKind::Insert);
+        if !replaced {
+            self.trigger_lifecycle(id, LifecycleEvents::added);
+        }
+    }
+
+    fn trigger_lifecycle(&mut self, id: TypeId, event: fn(&LifecycleEvents) -> TriggeredEvent) {
+        if let Some(events) = self.hooks.get(&id).and_then(ResourceHooks::events) {
+            self.triggered.push(event(&events));
+        }
+    }
+
+    fn register_lifecycle<T: Resource + 'static>(&mut self) {
+        let plugin = self.current_plugin();
+        self.hooks.entry(T::id()).or_default().set_events(LifecycleEvents::of::<T>(), plugin);
     }
 
     pub fn startup(&mut self) {
-        if let Some(startup) = self.systems.remove(&Startup.order()) {
-            for mut s in startup {
-                s.run(&mut self.resources);
+        if self.started {
+            warn!("startup already ran");
+            return;
+        }
+        self.started = true;
+        if let Some(mut pending) = self.systems.remove(&Startup.order()) {
+            self.run_systems(Startup.order(), &mut pending);
+        }
+    }
+
+    pub fn run(&mut self) {
+        if !self.late_startup.is_empty() {
+            let mut pending = std::mem::take(&mut self.late_startup);
+            self.run_systems(Startup.order(), &mut pending);
+        }
+        let mut sorted: Vec<_> = self
+            .systems
+            .keys()
+            .copied()
+            .filter(|order| *order != Startup.order() && !self.on_demand.contains(order))
+            .collect();
+        sorted.sort();
+        for order in sorted {
+            self.run_label(order);
+        }
+    }
+
+    pub fn run_schedule(&mut self, label: &dyn Label) {
+        self.run_label(label.order());
+    }
+
+    /// Runs `label` with `input` available as a resource, it is removed again afterwards
+    /// so every run sees only its own input.
+    pub fn run_schedule_with<T: Resource + 'static>(&mut self, label: &dyn Label, input: T) {
+        let previous = self.remove_resource::<T>();
+        self.add_resource(input);
+        self.run_label(label.order());
+        self.remove_resource::<T>();
+        if let Some(previous) = previous {
+            self.add_resource(previous);
+        }
+    }
+
+    fn run_label(&mut self, order: usize) {
+        let Some(systems) = self.systems.get_mut(&order) else {
+            return;
+        };
+        let mut running = std::mem::take(systems);
+        self.run_systems(order, &mut running);
+        // keep systems that were added to this label while it was running
+        let added = self.systems.insert(order, running).unwrap_or_default();
+        self.systems.entry(order).or_default().extend(added);
+    }
+
+    fn run_systems(&mut self, order: usize, systems: &mut [ScheduledSystem]) {
+        let start = Instant::now();
+        for s in systems {
+            s.run(self);
+        }
+        if let Some(mut profile) = self.resources.get_mut::<SystemProfile>() {
+            let label = self.labels.get(&order).map(String::as_str).unwrap_or("unnamed");
+            profile.record(label, ProfileKind::Label, None, start, start.elapsed());
+        }
+    }
+
+    pub fn run_events(&mut self, event: ApplicationEvent, event_loop: &dyn EventLoopContext) {
+        if let Some(mut events) = self.resources.get_mut::<Events<ApplicationEvent>>() {
+            events.send(event.clone());
+        }
+        for scheduled in &mut self.window_event_handler {
+            let previous = run_as(&mut self.resources, Some(scheduled.system.name()), scheduled.plugin);
+            scheduled.system.run( event.clone(), event_loop, &mut self.resources);
+            restore_running(&mut self.resources, previous);
+        }
+    }
+
+    pub fn apply_commands(&mut self) {
+        for _ in 0..MAX_TRIGGER_ROUNDS {
+            self.flush_commands();
+            let triggered = std::mem::take(&mut self.triggered);
+            if triggered.is_empty() {
+                return;
+            }
+            for event in triggered {
+                self.run_observers(event);
             }
         }
+        warn!(
+            "observers were still triggering events after {} command flushes, dropping {} events",
+            MAX_TRIGGER_ROUNDS,
+            self.triggered.len()
+        );
+        self.triggered.clear();
+    }
+
+    fn flush_commands(&mut self) {
+        let queued = self
+            .get_resource_mut::<CommandList>()
+            .map(|mut list| list.take())
+            .unwrap_or_default();
+        let finished = self
+            .get_resource::<CommandQueue>()
+            .map(|queue| queue.drain())
+            .unwrap_or_default();
+        // a command runs as the plugin that queued it
+        for QueuedCommand { command, source, plugin } in queued.into_iter().chain(finished) {
+            self.command_source = source;
+            let previous = self.set_current_plugin(plugin);
+            command.execute_boxed(self);
+            self.set_current_plugin(previous);
+        }
+        self.command_source = None;
+    }
+
+    pub fn add_observer<T: IntoIoSystem<I>, I>(&mut self, system: T)
+    where
+        T::System: 'static,
+        <T::System as IoSystem>::In: Clone + 'static,
+        <T::System as IoSystem>::Out: IntoResult,
+    {
+        let observer = ScheduledObserver {
+            id: SystemId::new(),
+            system: Box::new(Observer::new(system.into_io_system())),
+            plugin: self.current_plugin(),
+        };
+        self.observers
+            .entry(TypeId::of::<<T::System as IoSystem>::In>())
+            .or_default()
+            .push(observer);
+    }
+
+    /// Queues the event for every observer of `E`, they run once the current command flush is done.
+    pub fn trigger<E: 'static>(&mut self, event: E) {
+        self.triggered.push(TriggeredEvent {
+            event: Box::new(event),
+            name: std::any::type_name::<E>(),
+        });
+    }
+
+    fn run_observers(&mut self, triggered: TriggeredEvent) {
+        let id = (*triggered.event).type_id();
+        let Some(observers) = self.observers.get_mut(&id) else {
+            debug!("no observers for {}", triggered.name);
+            return;
+        };
+        let mut running = std::mem::take(observers);
+        for observer in &mut running {
+            observer.run(triggered.event.as_ref(), &mut self.resources);
+        }
+        let added = self.observers.insert(id, running).unwrap_or_default();
+        self.observers.entry(id).or_default().extend(added);
+    }
+
+    pub fn report_command_error(&mut self, command: &'static str, message: String) {
+        let error = CommandError {
+            command,
+            source: self.command_source,
+            message,
+        };
+        match self.get_resource_mut::<CommandErrorHandler>() {
+            Some(mut handler) => (handler.0)(&error),
+            None => log::error!("{}", error),
+        }
     }
 
-    pub fn run(&mut self) -> () {
-        let mut sorted: Vec<_> = self.systems.iter_mut().collect();
-        sorted.sort_by_key(|k| k.0);
-        for (_, systems) in sorted {
-            for s in systems {
-                s.run(&mut self.resources);
+    pub fn resource_hooks<T: Resource + 'static>(&mut self) -> &mut ResourceHooks {
+        self.register_lifecycle::<T>();
+        let plugin = self.current_plugin();
+        let hooks = self.hooks.entry(T::id()).or_default();
+        hooks.set_plugin(plugin);
+        hooks
+    }
+
+    /// Drops everything tagged with `plugin`: systems, observers, hooks, delayed
+    /// commands, undo steps, rollback registrations, the error handlers it set and
+    /// the resources, keyed stores and keyed entries it introduced, whether while
+    /// building or later. Other resources it only overwrote stay. Queued commands
+    /// have to be applied before, they may be the plugin's.
+    pub fn remove_plugin(&mut self, plugin: &str) {
+        // removed before its hooks go, so they still see them
+        for id in self.resources.owned_by(plugin) {
+            self.remove_entity(id);
+        }
+        for hooks in self.hooks.values_mut() {
+            hooks.remove_plugin(plugin);
+        }
+        // its hooks and observers may still queue commands and add resources
+        self.apply_commands();
+        let owned = |tag: Option<&'static str>| tag == Some(plugin);
+        for systems in self.systems.values_mut() {
+            systems.retain(|system| !owned(system.plugin));
+        }
+        self.late_startup.retain(|system| !owned(system.plugin));
+        self.registered_systems.retain(|_, system| !owned(system.plugin));
+        self.window_event_handler.retain(|system| !owned(system.plugin));
+        for observers in self.observers.values_mut() {
+            observers.retain(|observer| !owned(observer.plugin));
+        }
+        for id in self.resources.owned_by(plugin) {
+            self.remove_entity(id);
+        }
+        self.apply_commands();
+        if let Some(mut timers) = self.resources.get_mut::<Timers>() {
+            timers.remove_plugin(plugin);
+        }
+        if let Some(mut stack) = self.resources.get_mut::<UndoStack>() {
+            stack.remove_plugin(plugin);
+        }
+        // the host can box the handler again but not the closure inside
+        for id in [CommandErrorHandler::id(), SystemErrorHandler::id()] {
+            if self.resources.writer_of(id) == Some(plugin) {
+                self.remove_entity(id);
             }
         }
+        let unregistered = self
+            .resources
+            .get_mut::<RollbackRegistry>()
+            .map(|mut registry| registry.remove_plugin(plugin))
+            .unwrap_or_default();
+        if let Some(mut buffer) = self.resources.get_mut::<RollbackBuffer>() {
+            buffer.forget(&unregistered);
+        }
+        self.resources.release(plugin);
+        info!("removed plugin {}", plugin);
     }
 
-    pub fn run_events(&mut self, event: ApplicationEvent, event_loop: &ActiveEventLoop) {
-        for system in &mut self.window_event_handler {
-            system.run( event.clone(), event_loop, &mut self.resources);
+    // hooks may add hooks of their own, those are kept after the ones that ran
+    fn run_hooks(&mut self, id: TypeId, kind: HookKind) {
+        let Some(hooks) = self.hooks.get_mut(&id) else {
+            return;
+        };
+        let mut running = std::mem::take(hooks.hooks_mut(kind));
+        for hook in &mut running {
+            let previous = self.set_current_plugin(hook.plugin);
+            (hook.hook)(self);
+            self.set_current_plugin(previous);
         }
+        let hooks = self.hooks.entry(id).or_default().hooks_mut(kind);
+        let added = std::mem::replace(hooks, running);
+        hooks.extend(added);
     }
 
     pub fn add_resource<T: Resource + 'static>(&mut self, resource: T) {
-        self.resources.add(resource);
+        self.register_lifecycle::<T>();
+        self.insert_with(T::id(), |resources| resources.add(resource));
+    }
+
+    /// Swaps the value of `T` in place and hands back the old one. Only the replace
+    /// and insert hooks run, inserting where there was none behaves like `add_resource`.
+    pub fn replace_resource<T: Resource + 'static>(&mut self, resource: T) -> Option<T> {
+        if !self.resources.contains::<T>() {
+            self.add_resource(resource);
+            return None;
+        }
+        self.run_hooks(T::id(), HookKind::Replace);
+        // a replace hook may have removed it
+        let Some(mut current) = self.resources.get_mut::<T>() else {
+            self.add_resource(resource);
+            return None;
+        };
+        let previous = std::mem::replace(&mut *current, resource);
+        drop(current);
+        self.run_hooks(T::id(), HookKind::Insert);
+        Some(previous)
+    }
+
+    pub fn init_resource<T: Resource + FromResources + 'static>(&mut self) {
+        if !self.resources.contains::<T>() {
+            let resource = T::from_resources(&mut self.resources);
+            self.add_resource(resource);
+        }
+    }
+
+    /// [`Scheduler::init_resource`] for infrastructure every plugin uses, it
+    /// stays with the host when the plugin that created it is removed.
+    pub fn init_shared_resource<T: Resource + FromResources + 'static>(&mut self) {
+        self.resources.share::<T>();
+        self.init_resource::<T>();
+    }
+
+    pub fn add_keyed_resource<K: Hash + Eq + 'static, T: Resource + 'static>(&mut self, key: K, resource: T) {
+        self.resources.add_keyed(key, resource);
+    }
+
+    pub fn remove_keyed_resource<K: Hash + Eq + 'static, T: Resource + 'static>(&mut self, key: &K) -> Option<T> {
+        self.resources.remove_keyed::<K, T>(key)
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    pub fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
     }
 
     pub fn get_resource<T: Resource + 'static>(&self) -> Option<Res<T>> {
@@ -138,10 +647,192 @@ impl Scheduler {
         self.resources.get_mut::<T>()
     }
 
+    pub fn get_many_mut<S: ResourceSet>(&mut self) -> Option<S::Mut<'_>> {
+        self.resources.get_many_mut::<S>()
+    }
+
+    /// Takes `T` out of the store while `f` runs, so `f` gets it next to a mutable scheduler.
+    /// `T` still counts as present: taking it out and putting it back fires no hooks,
+    /// inserting it inside `f` is a replace. Panics if `T` is missing.
+    pub fn resource_scope<T: Resource + 'static, R>(&mut self, f: impl FnOnce(&mut Scheduler, &mut T) -> R) -> R {
+        let resource = self
+            .resources
+            .remove::<T>()
+            .unwrap_or_else(|| panic!("resource_scope: resource {} does not exist", std::any::type_name::<T>()));
+        let owner = self.resources.owner_of(T::id());
+        let nested = !self.scoped.insert(T::id());
+        // puts the resource back even if `f` panics
+        let mut guard = ScopeGuard {
+            scheduler: self,
+            resource: Some(resource),
+            owner,
+            nested,
+        };
+        let ScopeGuard { scheduler, resource, .. } = &mut guard;
+        f(scheduler, resource.as_mut().unwrap())
+    }
+
+    pub fn register_rollback<T: Resource + Clone + 'static>(&mut self) {
+        self.resources.init_shared::<RollbackRegistry>();
+        let plugin = self.current_plugin();
+        self.resource_scope::<RollbackRegistry, _>(|_, registry| registry.register_for::<T>(plugin));
+    }
+
+    /// Copies every resource registered for rollback, tagged with the current frame.
+    pub fn snapshot(&mut self) -> Snapshot {
+        let frame = self.resources.get::<Time>().map(|time| time.frame()).unwrap_or(0);
+        self.resources.init_shared::<RollbackRegistry>();
+        self.resource_scope::<RollbackRegistry, _>(|scheduler, registry| {
+            registry.snapshot(frame, &scheduler.resources)
+        })
+    }
+
+    /// Restores the registered resources through the regular insert and remove
+    /// paths, so hooks and lifecycle events fire as if the values were set again.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.resources.init_shared::<RollbackRegistry>();
+        self.resource_scope::<RollbackRegistry, _>(|scheduler, registry| {
+            for (id, value) in registry.restored(snapshot) {
+                match value {
+                    Some(value) => scheduler.insert_entity((id, RefCell::new(value))),
+                    None => {
+                        scheduler.remove_entity(id);
+                    }
+                }
+            }
+        })
+    }
+
     pub fn remove_resource<T: Resource + 'static>(&mut self) -> Option<T> {
-        self.resources.remove::<T>()
+        self.register_lifecycle::<T>();
+        self.remove_entity(T::id())
+            .and_then(|removed| removed.downcast::<T>().ok())
+            .map(|removed| *removed)
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.labels.iter().map(|(order, label)| (*order, label.as_str()))
     }
 
+    pub fn systems(&self, order: usize) -> &[ScheduledSystem] {
+        self.systems.get(&order).map(Vec::as_slice).unwrap_or(&[])
+    }
 
+    pub fn is_on_demand(&self, order: usize) -> bool {
+        self.on_demand.contains(&order)
+    }
 
+    pub fn registered_systems(&self) -> impl Iterator<Item = (SystemId, &ScheduledSystem)> {
+        self.registered_systems.iter().map(|(id, system)| (*id, system))
+    }
+
+    pub fn window_event_systems(&self) -> &[ScheduledWindowEventSystem] {
+        &self.window_event_handler
+    }
+
+    pub fn observers(&self) -> impl Iterator<Item = &ScheduledObserver> {
+        self.observers.values().flatten()
+    }
+
+
+
+}
+
+pub fn apply_commands(scheduler: &mut Scheduler) {
+    scheduler.apply_commands();
+}
+
+/// Ends a `resource_scope` and puts its resource back, also when the scope unwinds.
+struct ScopeGuard<'a, T: Resource + 'static> {
+    scheduler: &'a mut Scheduler,
+    resource: Option<T>,
+    owner: Option<&'static str>,
+    nested: bool,
+}
+
+impl<T: Resource + 'static> Drop for ScopeGuard<'_, T> {
+    fn drop(&mut self) {
+        if !self.nested {
+            self.scheduler.scoped.remove(&T::id());
+        }
+        let Some(resource) = self.resource.take() else {
+            return;
+        };
+        if !self.scheduler.resources.contains::<T>() {
+            self.scheduler.resources.add(resource);
+            // still belongs to whoever introduced it, not to whoever ran the scope
+            self.scheduler.resources.set_owner_of(T::id(), self.owner);
+        } else if std::thread::panicking() {
+            // no hooks while unwinding, a panicking hook would abort
+            self.scheduler.resources.add(resource);
+        } else {
+            warn!("resource {} was inserted during its own resource_scope, overwriting it", std::any::type_name::<T>());
+            self.scheduler.add_resource(resource);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+
+    use super::*;
+    use crate::{
+        system::{
+            commands::Command,
+            undo::{Record, SetResource},
+            SystemParam,
+        },
+        ChannelResource, EventReader,
+    };
+
+    struct Counter(u32);
+
+    #[test]
+    fn resource_scope_restores_on_panic() {
+        let mut scheduler = Scheduler::new();
+        scheduler.add_resource(Counter(1));
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            scheduler.resource_scope::<Counter, ()>(|_, counter| {
+                counter.0 += 1;
+                panic!("scope failed");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(scheduler.get_resource::<Counter>().map(|counter| counter.0), Some(2));
+        assert!(scheduler.scoped.is_empty());
+        // the scope can be entered again
+        scheduler.resource_scope::<Counter, _>(|_, counter| counter.0 += 1);
+        assert_eq!(scheduler.get_resource::<Counter>().map(|counter| counter.0), Some(3));
+    }
+
+    struct Introduced;
+
+    #[test]
+    fn remove_plugin_keeps_shared_resources() {
+        let mut scheduler = Scheduler::new();
+        scheduler.add_keyed_resource(0u32, Counter(0));
+        scheduler.add_resource(CommandErrorHandler::new(|_| {}));
+        Record(Box::new(SetResource { resource: Counter(1) })).execute(&mut scheduler);
+
+        let previous = scheduler.set_current_plugin(Some("plugin"));
+        Record(Box::new(SetResource { resource: Counter(2) })).execute(&mut scheduler);
+        <EventReader<u32> as SystemParam>::init_state(scheduler.resources_mut());
+        scheduler.init_shared_resource::<Timers>();
+        scheduler.add_keyed_resource(1u32, Counter(1));
+        scheduler.add_resource(CommandErrorHandler::new(|_| {}));
+        scheduler.add_resource(Introduced);
+        scheduler.set_current_plugin(previous);
+
+        scheduler.remove_plugin("plugin");
+        assert!(!scheduler.resources.contains::<Introduced>());
+        assert!(scheduler.resources.contains::<ChannelResource<u32>>());
+        assert!(scheduler.resources.contains::<Timers>());
+        let stack = scheduler.get_resource::<UndoStack>().unwrap();
+        assert_eq!(stack.undo_names().count(), 1);
+        drop(stack);
+        assert!(scheduler.resources.get_keyed::<u32, Counter>(&0).is_some());
+        assert!(scheduler.resources.get_keyed::<u32, Counter>(&1).is_none());
+        assert!(!scheduler.resources.contains::<CommandErrorHandler>());
+    }
 }
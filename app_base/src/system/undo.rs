@@ -0,0 +1,256 @@
+use std::marker::PhantomData;
+
+use log::debug;
+
+use crate::{
+    storage::{ResMut, Resource},
+    system::{commands::Command, scheduler::Scheduler},
+};
+
+/// A command that hands back the command undoing it when it runs.
+pub trait ReversibleCommand {
+    fn execute_reversible(self: Box<Self>, scheduler: &mut Scheduler) -> Box<dyn ReversibleCommand>;
+
+    fn name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+}
+
+pub type BoxedReversible = Box<dyn ReversibleCommand>;
+
+/// Inserts `T`, undoing it puts back the previous value or removes `T` again.
+pub struct SetResource<T> {
+    pub resource: T,
+}
+
+impl<T: Resource + 'static> ReversibleCommand for SetResource<T> {
+    fn execute_reversible(self: Box<Self>, scheduler: &mut Scheduler) -> BoxedReversible {
+        match scheduler.replace_resource(self.resource) {
+            Some(resource) => Box::new(SetResource { resource }),
+            None => Box::new(UnsetResource::<T> { marker: PhantomData }),
+        }
+    }
+}
+
+/// Removes `T`, undoing it inserts the removed value again.
+pub struct UnsetResource<T> {
+    pub marker: PhantomData<T>,
+}
+
+impl<T> UnsetResource<T> {
+    pub fn new() -> Self {
+        UnsetResource { marker: PhantomData }
+    }
+}
+
+impl<T> Default for UnsetResource<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Resource + 'static> ReversibleCommand for UnsetResource<T> {
+    fn execute_reversible(self: Box<Self>, scheduler: &mut Scheduler) -> BoxedReversible {
+        match scheduler.remove_resource::<T>() {
+            Some(resource) => Box::new(SetResource { resource }),
+            None => Box::new(UnsetResource::<T>::new()),
+        }
+    }
+}
+
+// the plugin whose code boxed the command, it can't outlive that plugin
+type Recorded = (BoxedReversible, Option<&'static str>);
+
+/// A group of commands undone and redone together, stored as the commands that revert it.
+pub struct Transaction {
+    pub name: String,
+    commands: Vec<Recorded>,
+}
+
+impl Transaction {
+    fn new(name: String) -> Self {
+        Transaction { name, commands: vec![] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    // runs the commands newest first, their inverses come out in the order
+    // that makes the returned transaction revert this one the same way
+    fn revert(self, scheduler: &mut Scheduler) -> Transaction {
+        let mut reverted = Transaction::new(self.name);
+        for (command, plugin) in self.commands.into_iter().rev() {
+            debug!("reverting {}", command.name());
+            reverted.commands.push((command.execute_reversible(scheduler), plugin));
+        }
+        reverted
+    }
+
+    fn remove_plugin(&mut self, plugin: &str) {
+        self.commands.retain(|(_, recorded_by)| *recorded_by != Some(plugin));
+    }
+}
+
+pub struct UndoStack {
+    undo: Vec<Transaction>,
+    redo: Vec<Transaction>,
+    open: Option<Transaction>,
+    limit: usize,
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl UndoStack {
+    /// Keeps at most `limit` transactions, the oldest are forgotten first. A
+    /// `limit` of 0 keeps every transaction.
+    pub fn new(limit: usize) -> Self {
+        UndoStack {
+            undo: vec![],
+            redo: vec![],
+            open: None,
+            limit,
+        }
+    }
+
+    /// Everything recorded until [`UndoStack::commit`] is undone as one step.
+    pub fn begin(&mut self, name: impl Into<String>) {
+        if self.open.is_some() {
+            self.commit();
+        }
+        self.open = Some(Transaction::new(name.into()));
+    }
+
+    pub fn commit(&mut self) {
+        if let Some(transaction) = self.open.take() {
+            self.push_undo(transaction);
+        }
+    }
+
+    /// `name` labels the step when no transaction is open. `plugin` is the
+    /// plugin whose code created `inverse`.
+    pub fn record(&mut self, plugin: Option<&'static str>, name: &str, inverse: BoxedReversible) {
+        self.redo.clear();
+        match &mut self.open {
+            Some(transaction) => transaction.commands.push((inverse, plugin)),
+            None => {
+                let mut transaction = Transaction::new(name.to_string());
+                transaction.commands.push((inverse, plugin));
+                self.push_undo(transaction);
+            }
+        }
+    }
+
+    fn push_undo(&mut self, transaction: Transaction) {
+        if transaction.is_empty() {
+            return;
+        }
+        self.undo.push(transaction);
+        if self.limit != 0 && self.undo.len() > self.limit {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo_names(&self) -> impl Iterator<Item = &str> {
+        self.undo.iter().rev().map(|transaction| transaction.name.as_str())
+    }
+
+    pub fn redo_names(&self) -> impl Iterator<Item = &str> {
+        self.redo.iter().rev().map(|transaction| transaction.name.as_str())
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.open = None;
+    }
+
+    /// Forgets the commands `plugin` recorded, transactions left empty are dropped.
+    pub fn remove_plugin(&mut self, plugin: &str) {
+        for transaction in self.undo.iter_mut().chain(self.redo.iter_mut()).chain(self.open.iter_mut()) {
+            transaction.remove_plugin(plugin);
+        }
+        self.undo.retain(|transaction| !transaction.is_empty());
+        self.redo.retain(|transaction| !transaction.is_empty());
+    }
+}
+
+fn undo_stack(scheduler: &mut Scheduler) -> ResMut<'_, UndoStack> {
+    scheduler.init_shared_resource::<UndoStack>();
+    scheduler.get_resource_mut::<UndoStack>().unwrap()
+}
+
+/// Runs a reversible command and records its inverse on the [`UndoStack`].
+pub struct Record(pub BoxedReversible);
+
+impl Command for Record {
+    fn execute(self, scheduler: &mut Scheduler) {
+        let name = self.0.name();
+        let inverse = self.0.execute_reversible(scheduler);
+        let plugin = scheduler.current_plugin();
+        undo_stack(scheduler).record(plugin, name, inverse);
+    }
+}
+
+pub struct BeginTransaction(pub String);
+
+impl Command for BeginTransaction {
+    fn execute(self, scheduler: &mut Scheduler) {
+        undo_stack(scheduler).begin(self.0);
+    }
+}
+
+pub struct CommitTransaction;
+
+impl Command for CommitTransaction {
+    fn execute(self, scheduler: &mut Scheduler) {
+        undo_stack(scheduler).commit();
+    }
+}
+
+pub struct Undo;
+
+impl Command for Undo {
+    fn execute(self, scheduler: &mut Scheduler) {
+        let popped = {
+            let mut stack = undo_stack(scheduler);
+            stack.commit();
+            stack.undo.pop()
+        };
+        let Some(transaction) = popped else {
+            debug!("nothing to undo");
+            return;
+        };
+        let redo = transaction.revert(scheduler);
+        undo_stack(scheduler).redo.push(redo);
+    }
+}
+
+pub struct Redo;
+
+impl Command for Redo {
+    fn execute(self, scheduler: &mut Scheduler) {
+        let Some(transaction) = undo_stack(scheduler).redo.pop() else {
+            debug!("nothing to redo");
+            return;
+        };
+        let undo = transaction.revert(scheduler);
+        undo_stack(scheduler).undo.push(undo);
+    }
+}
@@ -1,10 +1,10 @@
 use std::marker::PhantomData;
 
 
-use crate::{storage::Resources, system::{IntoWindowEventSystem, SystemParam, WindowEventSystem, ApplicationEvent}};
+use crate::{storage::Resources, system::{IntoWindowEventSystem, SystemAccess, SystemParam, WindowEventSystem, ApplicationEvent}};
 
 
-use winit::event_loop::ActiveEventLoop;
+use crate::context::EventLoopContext;
 
 
 
@@ -12,10 +12,11 @@ use winit::event_loop::ActiveEventLoop;
 
 
 
-pub struct WindowEventSystemFunction<F, In>
+pub struct WindowEventSystemFunction<F, P: SystemParam>
 {
     f: F,
-    marker: PhantomData<fn() -> In>
+    state: Option<P::State>,
+    marker: PhantomData<fn() -> P>
 
 }
 
@@ -29,21 +30,31 @@ macro_rules! impl_window_event_system_function {
             non_snake_case,
             reason = "Certain variable names are provided by the caller, not by us."
         )]
-        impl< F: FnMut(ApplicationEvent, & ActiveEventLoop, $($ty,)*), $($ty,)*> WindowEventSystem for WindowEventSystemFunction<F, (ApplicationEvent, & ActiveEventLoop, $($ty),*)>
+        impl< F: FnMut(ApplicationEvent, &dyn EventLoopContext, $($ty,)*), $($ty,)*> WindowEventSystem for WindowEventSystemFunction<F, ($($ty,)*)>
         where 
             $($ty : SystemParam,)*
-            for<'a, 'b> &'a mut F: FnMut(ApplicationEvent, & ActiveEventLoop, $($ty,)*) + FnMut(ApplicationEvent, & ActiveEventLoop, $(<$ty as SystemParam>::Item<'b>,)*) { 
-            fn run(&mut self, _window_event: ApplicationEvent, _event_loop: & ActiveEventLoop, _resources: &mut Resources) {
-               
-                $({
-                    $ty::prepare(_resources);
-
-                })*
-                
-                fn call_inner< $($ty,)*>(mut f: impl FnMut(ApplicationEvent, & ActiveEventLoop, $($ty,)*), window_event: ApplicationEvent, event_loop: & ActiveEventLoop, $($ty: $ty,)*) {
+            for<'a, 'b> &'a mut F: FnMut(ApplicationEvent, &dyn EventLoopContext, $($ty,)*) + FnMut(ApplicationEvent, &dyn EventLoopContext, $(<$ty as SystemParam>::Item<'b>,)*) { 
+            fn run(&mut self, window_event: ApplicationEvent, event_loop: &dyn EventLoopContext, resources: &mut Resources) {
+                <($($ty,)*) as SystemParam>::prepare(resources);
+                let state = self
+                    .state
+                    .get_or_insert_with(|| <($($ty,)*) as SystemParam>::init_state(resources));
+                let ($($ty,)*) = <($($ty,)*) as SystemParam>::param(state, resources);
+
+                fn call_inner< $($ty,)*>(mut f: impl FnMut(ApplicationEvent, &dyn EventLoopContext, $($ty,)*), window_event: ApplicationEvent, event_loop: &dyn EventLoopContext, $($ty: $ty,)*) {
                     f(window_event, event_loop, $($ty,)*);
                 }
-                call_inner(&mut self.f, _window_event, _event_loop, $($ty::param(_resources),)*)
+                call_inner(&mut self.f, window_event, event_loop, $($ty,)*)
+            }
+
+            fn name(&self) -> &'static str {
+                std::any::type_name::<F>()
+            }
+
+            fn access(&self) -> SystemAccess {
+                let mut _access = SystemAccess::default();
+                $($ty::access(&mut _access);)*
+                _access
             }
         }
 
@@ -67,13 +78,13 @@ macro_rules! impl_into_window_event_system {
             non_snake_case,
             reason = "Certain variable names are provided by the caller, not by us."
         )]
-        impl< F: FnMut(ApplicationEvent, & ActiveEventLoop, $($ty,)*), $($ty,)*> IntoWindowEventSystem<($($ty,)*)> for F
+        impl< F: FnMut(ApplicationEvent, &dyn EventLoopContext, $($ty,)*), $($ty,)*> IntoWindowEventSystem<($($ty,)*)> for F
         where 
             $($ty : SystemParam,)*
-            for<'a, 'b> &'a mut F: FnMut(ApplicationEvent, & ActiveEventLoop, $($ty,)*) + FnMut(ApplicationEvent, & ActiveEventLoop, $(<$ty as SystemParam>::Item<'b>,)*) { 
-            type System<'new> = WindowEventSystemFunction<F, (ApplicationEvent, &'new ActiveEventLoop, $($ty),*)>;
+            for<'a, 'b> &'a mut F: FnMut(ApplicationEvent, &dyn EventLoopContext, $($ty,)*) + FnMut(ApplicationEvent, &dyn EventLoopContext, $(<$ty as SystemParam>::Item<'b>,)*) { 
+            type System<'new> = WindowEventSystemFunction<F, ($($ty,)*)>;
             fn into_system<'r>(self) -> Self::System<'r> {
-                WindowEventSystemFunction{ f: self, marker: PhantomData }
+                WindowEventSystemFunction{ f: self, state: None, marker: PhantomData }
             }
         }
     };
@@ -0,0 +1,346 @@
+use std::time::{Duration, Instant};
+
+use crate::system::{
+    IntoSystem, System, SystemAccess,
+    commands::{Command, QueuedCommand},
+    scheduler::Scheduler,
+};
+use crate::{App, Plugin, storage::Resources};
+
+#[derive(Clone)]
+pub struct Time {
+    delta: Duration,
+    elapsed: Duration,
+    frame: u64,
+    last: Option<Instant>,
+    fixed_delta: Option<Duration>,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Time {
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            frame: 0,
+            last: None,
+            fixed_delta: None,
+        }
+    }
+
+    /// Every frame advances the clock by exactly `delta`, regardless of wall time.
+    pub fn with_fixed_delta(mut self, delta: Duration) -> Self {
+        self.fixed_delta = Some(delta);
+        self
+    }
+
+    pub fn set_fixed_delta(&mut self, delta: Option<Duration>) {
+        self.fixed_delta = delta;
+    }
+
+    pub fn fixed_delta(&self) -> Option<Duration> {
+        self.fixed_delta
+    }
+
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        self.delta = match self.fixed_delta {
+            Some(delta) => delta,
+            None => self.last.map(|last| now - last).unwrap_or_default(),
+        };
+        self.last = Some(now);
+        self.elapsed += self.delta;
+        self.frame += 1;
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn delta_secs(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerMode {
+    Once,
+    Repeating,
+}
+
+#[derive(Debug, Clone)]
+pub struct Timer {
+    duration: Duration,
+    elapsed: Duration,
+    mode: TimerMode,
+    finished: bool,
+    times_finished: u32,
+}
+
+impl Timer {
+    pub fn new(duration: Duration, mode: TimerMode) -> Self {
+        Timer {
+            duration,
+            elapsed: Duration::ZERO,
+            mode,
+            finished: false,
+            times_finished: 0,
+        }
+    }
+
+    pub fn once(duration: Duration) -> Self {
+        Self::new(duration, TimerMode::Once)
+    }
+
+    pub fn repeating(duration: Duration) -> Self {
+        Self::new(duration, TimerMode::Repeating)
+    }
+
+    pub fn tick(&mut self, delta: Duration) -> &Self {
+        self.times_finished = 0;
+        if self.mode == TimerMode::Once && self.finished {
+            return self;
+        }
+        self.elapsed += delta;
+        if self.elapsed < self.duration {
+            return self;
+        }
+        self.finished = true;
+        match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                self.times_finished = 1;
+            }
+            TimerMode::Repeating if self.duration.is_zero() => {
+                self.elapsed = Duration::ZERO;
+                self.times_finished = 1;
+            }
+            TimerMode::Repeating => {
+                self.times_finished = (self.elapsed.as_nanos() / self.duration.as_nanos()) as u32;
+                self.elapsed = Duration::from_nanos(
+                    (self.elapsed.as_nanos() % self.duration.as_nanos()) as u64,
+                );
+            }
+        }
+        self
+    }
+
+    /// True only for the tick on which the timer (re)completed.
+    pub fn just_finished(&self) -> bool {
+        self.times_finished > 0
+    }
+
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn times_finished(&self) -> u32 {
+        self.times_finished
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.finished = false;
+        self.times_finished = 0;
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+}
+
+struct DelayedCommand {
+    timer: Timer,
+    command: QueuedCommand,
+}
+
+pub struct Timers {
+    delayed: Vec<DelayedCommand>,
+}
+
+impl Default for Timers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Timers {
+    pub fn new() -> Self {
+        Timers {
+            delayed: Vec::new(),
+        }
+    }
+
+    pub fn after<T: Command + 'static>(&mut self, duration: Duration, command: T) {
+        self.after_queued(
+            duration,
+            QueuedCommand {
+                command: Box::new(command),
+                source: None,
+                plugin: None,
+            },
+        );
+    }
+
+    pub fn after_queued(&mut self, duration: Duration, command: QueuedCommand) {
+        self.delayed.push(DelayedCommand {
+            timer: Timer::once(duration),
+            command,
+        });
+    }
+
+    /// Advances every delayed command and returns the ones that are due, in the order they were added.
+    pub fn tick(&mut self, delta: Duration) -> Vec<QueuedCommand> {
+        let mut due = Vec::new();
+        let mut pending = Vec::with_capacity(self.delayed.len());
+        for mut delayed in self.delayed.drain(..) {
+            if delayed.timer.tick(delta).finished() {
+                due.push(delayed.command);
+            } else {
+                pending.push(delayed);
+            }
+        }
+        self.delayed = pending;
+        due
+    }
+
+    pub fn len(&self) -> usize {
+        self.delayed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.delayed.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.delayed.clear();
+    }
+
+    /// Drops the commands `plugin` delayed, they can't run once it is unloaded.
+    pub fn remove_plugin(&mut self, plugin: &str) {
+        self.delayed.retain(|delayed| delayed.command.plugin != Some(plugin));
+    }
+}
+
+pub struct Delay {
+    pub duration: Duration,
+    pub command: QueuedCommand,
+}
+
+impl Command for Delay {
+    fn execute(self, scheduler: &mut Scheduler) {
+        scheduler.init_shared_resource::<Timers>();
+        if let Some(mut timers) = scheduler.get_resource_mut::<Timers>() {
+            timers.after_queued(self.duration, self.command);
+        }
+    }
+}
+
+pub struct RunEvery<S> {
+    system: S,
+    timer: Timer,
+}
+
+impl<S: System> System for RunEvery<S> {
+    fn run(&mut self, resources: &mut Resources) {
+        let Some(delta) = resources.get::<Time>().map(|time| time.delta()) else {
+            return;
+        };
+        if self.timer.tick(delta).just_finished() {
+            self.system.run(resources);
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        self.system.name()
+    }
+
+    fn access(&self) -> SystemAccess {
+        let mut access = self.system.access();
+        access.read::<Time>();
+        access
+    }
+
+    fn is_exclusive(&self) -> bool {
+        self.system.is_exclusive()
+    }
+
+    fn run_exclusive(&mut self, scheduler: &mut Scheduler) {
+        let Some(delta) = scheduler.get_resource::<Time>().map(|time| time.delta()) else {
+            return;
+        };
+        if self.timer.tick(delta).just_finished() {
+            self.system.run_exclusive(scheduler);
+        }
+    }
+}
+
+pub struct RunEveryMarker;
+
+impl<S: System> IntoSystem<RunEveryMarker> for RunEvery<S> {
+    type System = Self;
+    fn into_system(self) -> Self::System {
+        self
+    }
+}
+
+pub trait RunEveryExt<I>: IntoSystem<I> + Sized {
+    fn run_every(self, duration: Duration) -> RunEvery<Self::System> {
+        RunEvery {
+            system: self.into_system(),
+            timer: Timer::repeating(duration),
+        }
+    }
+}
+
+impl<T: IntoSystem<I>, I> RunEveryExt<I> for T {}
+
+/// Configures the `Time` and `Timers` that `App::new` already adds, for
+/// example to run with a fixed timestep. A `fixed_delta` of `None` keeps
+/// whatever timestep is already set.
+#[derive(Default)]
+pub struct TimePlugin {
+    pub fixed_delta: Option<Duration>,
+}
+
+impl TimePlugin {
+    pub fn fixed(delta: Duration) -> Self {
+        TimePlugin {
+            fixed_delta: Some(delta),
+        }
+    }
+}
+
+impl Plugin for TimePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Time>();
+        app.init_resource::<Timers>();
+        // leave a timestep another plugin already configured alone
+        if let Some(delta) = self.fixed_delta
+            && let Some(mut time) = app.scheduler.get_resource_mut::<Time>()
+        {
+            time.set_fixed_delta(Some(delta));
+        }
+    }
+}
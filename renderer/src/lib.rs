@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::Display,
     ops::Range,
     sync::{
@@ -9,27 +9,63 @@ use std::{
 };
 
 use app_base::{
-    event::WindowEvent, event_loop::ActiveEventLoop, math::{Vec3, Vec4}, runtime::Runtime, storage::{Res, ResMut}, system::{commands::{Command, Commands}, scheduler::Update, IntoSystem}, window::{Window, WindowId}, App, ApplicationEvent, Plugin
+    event::WindowEvent, context::EventLoopContext, logging::LogPlugin, math::{Vec3, Vec4}, plugin_group::{PluginGroup, PluginGroupBuilder}, storage::{Res, ResMut}, runtime::Runtime, system::{commands::{Command, CommandSender, Commands, TryCommand}, scheduler::Update, IntoSystem}, time::TimePlugin, window::{Window, WindowId}, App, ApplicationEvent, Plugin
 };
-use log::info;
+use log::{error, info};
 pub use wgpu::*;
 pub use wgpu::{
     util::{BufferInitDescriptor, DeviceExt},
     wgc::device::queue,
     wgt::{BufferDescriptor, CommandEncoderDescriptor},
 };
-use window::Windows;
+use window::{OnWindowCreated, WindowPlugin, Windows, input::InputPlugin};
 pub struct RendererPlugin;
 
+/// Log, time, window, input and renderer plugins with their defaults.
+pub struct DefaultPlugins;
+
+impl PluginGroup for DefaultPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::new()
+            .add_plugin(LogPlugin::default())
+            .add_plugin(TimePlugin::default())
+            .add_plugin(WindowPlugin::default())
+            .add_plugin(InputPlugin)
+            .add_plugin(RendererPlugin::new())
+    }
+}
+
 impl RendererPlugin {
     pub fn new() -> Self {
         Self
     }
 }
 
+pub struct OnRenderResourceReady;
+
+impl app_base::system::scheduler::Label for OnRenderResourceReady {
+    fn label(&self) -> &str {
+        "OnRenderResourceReady"
+    }
+
+    fn order(&self) -> usize {
+        OnWindowCreated.order() + 1
+    }
+
+    fn on_demand(&self) -> bool {
+        true
+    }
+}
+
+/// Available while [`OnRenderResourceReady`] runs for the window whose resources are ready.
+pub struct ReadyRenderResource(pub WindowId);
+
 pub struct RenderResources {
     instance: Instance,
     resources: HashMap<WindowId, RenderResource>,
+    pending: HashSet<WindowId>,
+    /// windows whose surface could not be created, skipped until they close
+    failed: HashSet<WindowId>,
     main_resource_id: Option<WindowId>,
 }
 
@@ -81,6 +117,40 @@ impl Command for ClearMeshes {
     }
 }
 
+pub struct AddRenderResources {
+    pub resources: Vec<RenderResource>,
+    /// windows whose resources could not be created, retried on the next update
+    pub failed: Vec<WindowId>,
+}
+
+impl Command for AddRenderResources {
+    fn execute(self, scheduler: &mut app_base::system::scheduler::Scheduler) {
+        if let Some(mut render_resources) = scheduler.get_resource_mut::<RenderResources>() {
+            for id in &self.failed {
+                render_resources.pending.remove(id);
+            }
+        }
+        for resource in self.resources {
+            let id = resource.window_id;
+            let open = scheduler
+                .get_resource::<Windows>()
+                .is_some_and(|windows| windows.get_window(id).is_some());
+            {
+                let Some(mut render_resources) = scheduler.get_resource_mut::<RenderResources>()
+                else {
+                    continue;
+                };
+                render_resources.pending.remove(&id);
+                if !open {
+                    continue;
+                }
+                render_resources.add_resource(id, resource);
+            }
+            scheduler.run_schedule_with(&OnRenderResourceReady, ReadyRenderResource(id));
+        }
+    }
+}
+
 pub struct CopyBufferToBufferCommand {
     pub source: Buffer,
     pub destination: Buffer,
@@ -114,14 +184,22 @@ impl CopyBufferToBufferCommand {
     }
 }
 
-impl Command for CopyBufferToBufferCommand {
-    fn execute(self, scheduler: &mut app_base::system::scheduler::Scheduler) {
-        let windows = scheduler.get_resource::<Windows>().unwrap();
-        let mut render_resources = scheduler.get_resource_mut::<RenderResources>().unwrap();
+impl TryCommand for CopyBufferToBufferCommand {
+    type Error = String;
+
+    fn try_execute(self, scheduler: &mut app_base::system::scheduler::Scheduler) -> Result<(), String> {
+        let (windows, render_resources) = scheduler
+            .get_many_mut::<(Windows, RenderResources)>()
+            .ok_or("no Windows or RenderResources resource")?;
+        let window_id = self
+            .window_id
+            .or(windows.main_window)
+            .ok_or("no target window and no main window")?;
         let render_resource = render_resources
-            .get_resource_mut(&self.window_id.or_else(|| windows.main_window).unwrap())
-            .unwrap();
+            .get_resource_mut(&window_id)
+            .ok_or_else(|| format!("no render resource for window {:?}", window_id))?;
         self.execute_on_resources(render_resource);
+        Ok(())
     }
 }
 
@@ -149,7 +227,7 @@ impl<'a> CommandExt for Commands<'a> {
         size: u64,
         window_id: Option<WindowId>,
     ) -> &mut Self {
-        self.add_command(CopyBufferToBufferCommand {
+        self.try_command(CopyBufferToBufferCommand {
             source: source,
             destination: destination,
             source_offset: source_offset,
@@ -173,6 +251,12 @@ pub struct RenderMeshes {
     pub index_ranges: HashMap<MeshId, Range<u32>>,
 }
 
+impl Default for RenderMeshes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RenderMeshes {
     pub fn new() -> Self {
         Self {
@@ -309,6 +393,12 @@ impl RenderMeshes {
     }
 }
 
+impl Default for Meshes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Meshes {
     pub fn new() -> Self {
         Meshes {
@@ -356,6 +446,12 @@ impl Mesh {
 
 }
 
+impl Default for RenderResources {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RenderResources {
     pub fn new() -> Self {
         let instance = Instance::new(&InstanceDescriptor {
@@ -365,6 +461,8 @@ impl RenderResources {
         RenderResources {
             instance: instance,
             resources: HashMap::new(),
+            pending: HashSet::new(),
+            failed: HashSet::new(),
             main_resource_id: None,
         }
     }
@@ -408,9 +506,9 @@ impl RenderResources {
 
 impl Plugin for RendererPlugin {
     fn build(&self, app: &mut App) {
-        app.add_resource(RenderResources::new());
-        app.add_resource(Meshes::new());
-        app.add_resource(RenderMeshes::new());
+        app.init_resource::<RenderResources>();
+        app.init_resource::<Meshes>();
+        app.init_resource::<RenderMeshes>();
         app.add_systems( Update, (update, transfer_meshes));
         app.add_window_event_system(on_event);
     }
@@ -420,15 +518,51 @@ fn update(
     mut render_resources: ResMut<RenderResources>,
     windows: Res<Windows>,
     runtime: Res<Runtime>,
+    sender: CommandSender,
 ) {
     render_resources.main_resource_id = windows.main_window;
+    render_resources
+        .failed
+        .retain(|id| windows.windows.contains_key(id));
+    let mut pending = vec![];
     for window in &windows.windows {
-        if let Some(_) = render_resources.get_resource(window.0) {
+        if render_resources.get_resource(window.0).is_some()
+            || render_resources.pending.contains(window.0)
+            || render_resources.failed.contains(window.0)
+        {
             continue;
-        } else {
-            create_render_resource(&mut render_resources, window.1.clone(), &runtime);
+        }
+        // surfaces are created on the main thread, adapter and device requests are awaited on the runtime
+        match render_resources.instance.create_surface(window.1.clone()) {
+            Ok(surface) => {
+                render_resources.pending.insert(*window.0);
+                pending.push((window.1.clone(), surface));
+            }
+            Err(e) => {
+                error!("failed to create surface for {:?}: {}", window.0, e);
+                render_resources.failed.insert(*window.0);
+            }
         }
     }
+    if pending.is_empty() {
+        return;
+    }
+    let instance = render_resources.instance.clone();
+    runtime.spawn(async move {
+        let mut resources = vec![];
+        let mut failed = vec![];
+        for (window, surface) in pending {
+            let id = window.id();
+            match create_render_resource(&instance, window, surface).await {
+                Ok(resource) => resources.push(resource),
+                Err(e) => {
+                    error!("failed to create render resource for {:?}: {}", id, e);
+                    failed.push(id);
+                }
+            }
+        }
+        sender.send(AddRenderResources { resources, failed });
+    });
 }
 
 fn transfer_meshes(
@@ -444,33 +578,27 @@ fn transfer_meshes(
     }
 }
 
-fn create_render_resource(
-    render_resources: &mut RenderResources,
+async fn create_render_resource(
+    instance: &Instance,
     window: Arc<Window>,
-    runtime: &Runtime,
-) {
-    let surface = render_resources
-        .instance
-        .create_surface(window.clone())
-        .expect("Failed to create surface");
-    let adapter = runtime
-        .block_on(
-            render_resources
-                .instance
-                .request_adapter(&wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::HighPerformance,
-                    compatible_surface: Some(&surface),
-                    force_fallback_adapter: false,
-                }),
-        )
-        .expect("Failed to request adapter");
-    let (device, queue) = runtime
-        .block_on(adapter.request_device(&DeviceDescriptor {
+    surface: Surface<'static>,
+) -> Result<RenderResource, String> {
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            compatible_surface: Some(&surface),
+            force_fallback_adapter: false,
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    let (device, queue) = adapter
+        .request_device(&DeviceDescriptor {
             required_limits: adapter.limits(),
             required_features: adapter.features(),
             ..Default::default()
-        }))
-        .expect("Failed to request device");
+        })
+        .await
+        .map_err(|e| e.to_string())?;
 
     let config = surface
         .get_default_config(
@@ -478,25 +606,22 @@ fn create_render_resource(
             window.inner_size().width,
             window.inner_size().height,
         )
-        .expect("Failed to get default config");
+        .ok_or_else(|| "surface is not supported by the adapter".to_string())?;
     surface.configure(&device, &config);
 
-    render_resources.add_resource(
-        window.id(),
-        RenderResource {
-            surface: surface,
-            adapter: adapter,
-            device: device,
-            queue: queue,
-            surface_config: config,
-            window_id: window.id(),
-        },
-    );
+    Ok(RenderResource {
+        surface: surface,
+        adapter: adapter,
+        device: device,
+        queue: queue,
+        surface_config: config,
+        window_id: window.id(),
+    })
 }
 
 fn on_event(
     event: ApplicationEvent,
-    _event_loop: &ActiveEventLoop,
+    _event_loop: &dyn EventLoopContext,
     mut render_resources: ResMut<RenderResources>,
     mut meshes: ResMut<Meshes>,
     mut render_meshes: ResMut<RenderMeshes>
@@ -1,24 +1,162 @@
-use std::path::Path;
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 
+use app_base::{
+    App, Plugin,
+    storage::{Res, ResMut},
+    system::{
+        scheduler::{Scheduler, Update},
+        undo::{BoxedReversible, ReversibleCommand},
+    },
+    time::{RunEveryExt, Time, Timer},
+};
+use log::info;
 use naga::{
-    Arena, Constant, Expression, Handle, back::hlsl::Options, front::wgsl, valid::ValidationFlags,
+    Arena, Constant, Expression, Handle, Module, back::hlsl::Options, front::wgsl,
+    valid::ValidationFlags,
 };
 
-pub fn test<P1: AsRef<Path>>(path: P1) {
-    let parsed = std::fs::read_to_string(path).unwrap();
+#[derive(Debug)]
+pub enum ShaderError {
+    Io(PathBuf, std::io::Error),
+    Parse(String),
+    Validation(String),
+    Hlsl(String),
+}
+
+impl Display for ShaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShaderError::Io(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+            ShaderError::Parse(e) => write!(f, "failed to parse wgsl:\n{}", e),
+            ShaderError::Validation(e) => write!(f, "invalid shader: {}", e),
+            ShaderError::Hlsl(e) => write!(f, "failed to write hlsl: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
 
-    let shader = wgsl::parse_str(&parsed).unwrap();
+pub fn parse_wgsl(source: &str) -> Result<Module, ShaderError> {
+    wgsl::parse_str(source).map_err(|e| ShaderError::Parse(e.emit_to_string(source)))
+}
+
+pub fn load_wgsl<P: AsRef<Path>>(path: P) -> Result<Module, ShaderError> {
+    let path = path.as_ref();
+    let source =
+        std::fs::read_to_string(path).map_err(|e| ShaderError::Io(path.to_path_buf(), e))?;
+    parse_wgsl(&source)
+}
 
+pub fn to_hlsl(shader: &Module) -> Result<String, ShaderError> {
     let mut validator =
         naga::valid::Validator::new(ValidationFlags::all(), naga::valid::Capabilities::all());
-    let info = validator.validate(&shader).unwrap();
+    let info = validator
+        .validate(shader)
+        .map_err(|e| ShaderError::Validation(e.into_inner().to_string()))?;
 
     let opts = Options::default();
     let mut buffer = String::new();
     let mut writer = naga::back::hlsl::Writer::new(&mut buffer, &opts);
+    writer
+        .write(shader, &info, None)
+        .map_err(|e| ShaderError::Hlsl(e.to_string()))?;
+    Ok(buffer)
+}
+
+pub fn test<P1: AsRef<Path>>(path: P1) -> Result<(), ShaderError> {
+    let shader = load_wgsl(path)?;
     println!("Shader: {:#?}", shader);
-    let _ = writer.write(&shader, &info, None).unwrap();
-    println!("hsls: {}", buffer);
+    let hlsl = to_hlsl(&shader)?;
+    println!("hsls: {}", hlsl);
+    Ok(())
+}
+
+pub struct ShaderSource {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    debounce: Timer,
+}
+
+impl ShaderSource {
+    pub fn new<P: Into<PathBuf>>(path: P, debounce: Duration) -> Self {
+        let path = path.into();
+        ShaderSource {
+            modified: modified_time(&path),
+            path,
+            debounce: Timer::once(debounce),
+        }
+    }
+}
+
+/// Points the editor at another shader file, undoing it switches back.
+pub struct SetShaderPath(pub PathBuf);
+
+impl ReversibleCommand for SetShaderPath {
+    fn execute_reversible(self: Box<Self>, scheduler: &mut Scheduler) -> BoxedReversible {
+        let Some(mut source) = scheduler.get_resource_mut::<ShaderSource>() else {
+            return Box::new(UnchangedShaderPath);
+        };
+        let previous = std::mem::replace(&mut source.path, self.0);
+        source.modified = None;
+        info!("editing {}", source.path.display());
+        Box::new(SetShaderPath(previous))
+    }
+}
+
+/// The inverse of a `SetShaderPath` that found no `ShaderSource`, undoing and redoing it does nothing.
+struct UnchangedShaderPath;
+
+impl ReversibleCommand for UnchangedShaderPath {
+    fn execute_reversible(self: Box<Self>, _scheduler: &mut Scheduler) -> BoxedReversible {
+        self
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn watch_shader(mut source: ResMut<ShaderSource>) {
+    let modified = modified_time(&source.path);
+    if modified != source.modified {
+        source.modified = modified;
+        source.debounce.reset();
+    }
+}
+
+fn recompile_shader(mut source: ResMut<ShaderSource>, time: Res<Time>) -> Result<(), ShaderError> {
+    if !source.debounce.tick(time.delta()).just_finished() {
+        return Ok(());
+    }
+    let shader = load_wgsl(&source.path)?;
+    to_hlsl(&shader)?;
+    info!("recompiled {}", source.path.display());
+    Ok(())
+}
+
+pub struct ShaderCheckPlugin {
+    pub path: PathBuf,
+    pub debounce: Duration,
+}
+
+impl ShaderCheckPlugin {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        ShaderCheckPlugin {
+            path: path.into(),
+            debounce: Duration::from_millis(300),
+        }
+    }
+}
+
+impl Plugin for ShaderCheckPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_resource(ShaderSource::new(self.path.clone(), self.debounce));
+        app.add_systems(Update, (watch_shader.run_every(Duration::from_millis(100)), recompile_shader));
+    }
 }
 
 fn build_add_expression(left: Handle<Expression>, right: Handle<Expression>) -> Expression {
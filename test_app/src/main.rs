@@ -1,33 +1,49 @@
-use std::{borrow::Cow, num::NonZero};
+use std::{borrow::Cow, num::NonZero, time::{Duration, SystemTime}};
 
 use app_base::{
-    event::WindowEvent, event_loop::ActiveEventLoop, keyboard::{KeyCode, PhysicalKey}, math::Vec4, storage::{Res, ResMut}, system::{commands::Commands, scheduler::Update}, App, ApplicationEvent, Quit
+    event::WindowEvent, events::Events, keyboard::{KeyCode, PhysicalKey}, math::Vec4, storage::{Res, ResMut}, system::{commands::{Command, Commands}, pipe::In, scheduler::{SystemId, Update}}, plugin_group::PluginGroup, time::RunEveryExt, App, ApplicationEvent, Quit
 };
 use log::info;
 use renderer::{
-    include_wgsl, util::{BufferInitDescriptor, DeviceExt}, vertex_attr_array, wgc::device, wgt::{TextureDescriptor, TextureViewDescriptor}, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BlendState, BufferAddress, Color, ColorTargetState, ColorWrites, CommandEncoder, CommandEncoderDescriptor, CommandExt, ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Device, Extent3d, FragmentState, Mesh, MeshId, Meshes, MultisampleState, Operations, PipelineCompilationOptions, PipelineLayout, PipelineLayoutDescriptor, PollType, PrimitiveState, RenderMeshes, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, RenderResources, ShaderModule, ShaderModuleDescriptor, ShaderStages, Texture, TextureFormat, TextureUsages, VertexAttribute, VertexBufferLayout, VertexState
+    include_wgsl, util::{BufferInitDescriptor, DeviceExt}, vertex_attr_array, wgc::device, wgt::{TextureDescriptor, TextureViewDescriptor}, BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType, BlendState, BufferAddress, ClearMeshes, Color, DefaultPlugins, ColorTargetState, ColorWrites, CommandEncoder, CommandEncoderDescriptor, ComputePassDescriptor, ComputePipeline, ComputePipelineDescriptor, Device, Extent3d, FragmentState, Mesh, MeshId, Meshes, MultisampleState, OnRenderResourceReady, Operations, PipelineCompilationOptions, PipelineLayout, PipelineLayoutDescriptor, PollType, PrimitiveState, RenderMeshes, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, RenderResources, ShaderModule, ShaderModuleDescriptor, ShaderStages, Texture, TextureFormat, TextureUsages, VertexAttribute, VertexBufferLayout, VertexState
 };
-use window::{WindowConfigs, Windows, events};
+use window::{Windows, events::WindowClosedEvent};
 fn main() -> Result<(), String> {
     let mut app = App::new();
-    app.add_plugin(window::WindowPlugin::new("Test App", 800, 600));
-    app.add_plugin(renderer::RendererPlugin::new());
-    app.add_systems(Update, (entry, render, prepare_render_resources));
+    app.add_plugins(
+        DefaultPlugins
+            .set(window::WindowPlugin::new("Test App", 800, 600))
+            .map_err(|e| e.to_string())?,
+    );
+    app.add_systems(Update, (recreate, render));
+    app.add_observer(quit_on_last_window_closed);
+    app.add_systems(OnRenderResourceReady, prepare_when_ready);
+    // the quad mesh belongs to the compute resources, drop it with them
+    app.resource_hooks::<ComputeResources>()
+        .on_remove(|scheduler| ClearMeshes.execute(scheduler));
+    let prepare = app.register_system(prepare_render_resources);
+    app.add_resource(PrepareRenderResources(prepare));
+
+    app.add_systems(Update, reload_shaders.run_every(Duration::from_secs(1)));
+    app.add_resource(ShaderTimestamps::default());
 
-    app.add_window_event_system(recreate);
-    app.add_resource(WasEmpty { empty: true });
     app.run().map_err(|e| e.to_string())
 }
 
-struct WasEmpty {
-    empty: bool,
+struct PrepareRenderResources(SystemId);
+
+fn prepare_when_ready(mut commands: Commands, prepare: Res<PrepareRenderResources>) {
+    commands.run_system(prepare.0);
 }
 
-fn entry(mut commands: Commands, windows: ResMut<Windows>, mut was_empty: ResMut<WasEmpty>) {
-    if windows.is_empty() && was_empty.empty == false {
+fn quit_on_last_window_closed(
+    In(event): In<WindowClosedEvent>,
+    mut commands: Commands,
+    windows: Res<Windows>,
+) {
+    info!("window {:?} closed", event.window_id);
+    if windows.is_empty() {
         commands.insert_resource(Quit);
-    } else if !windows.is_empty() {
-        was_empty.empty = false;
     }
 }
 
@@ -56,27 +72,58 @@ pub struct ComputeResources {
     // pub triangle_index_buffer: Buffer,
 }
 
-fn recreate(event: ApplicationEvent, _event_loop: &ActiveEventLoop, mut commands: Commands) {
-    match event {
+fn recreate(
+    events: Res<Events<ApplicationEvent>>,
+    mut commands: Commands,
+    prepare: Res<PrepareRenderResources>,
+) {
+    let requested = events.iter().any(|event| match event {
         ApplicationEvent::WindowEvent { id: _, event } => match event {
             WindowEvent::KeyboardInput {
                 device_id: _,
                 event,
                 is_synthetic: _,
-            } => {
-                if event.physical_key == PhysicalKey::Code(KeyCode::KeyR) {
-                    commands.remove_resource::<ComputeResources>();
-                    commands.clear_meshes();
-                }
-            }
-            WindowEvent::Resized(_) => {
-                commands.remove_resource::<ComputeResources>();
-                commands.clear_meshes();
-
-            }
-            _ => {}
+            } => event.physical_key == PhysicalKey::Code(KeyCode::KeyR),
+            WindowEvent::Resized(_) => true,
+            _ => false,
         },
-        _ => {}
+        _ => false,
+    });
+    if requested {
+        commands.remove_resource::<ComputeResources>();
+        commands.run_system(prepare.0);
+    }
+}
+
+// resolved at build time so the app can be started from any directory
+const SHADERS: [&str; 2] = [
+    concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/compute.wgsl"),
+    concat!(env!("CARGO_MANIFEST_DIR"), "/shaders/shader.wgsl"),
+];
+
+#[derive(Default)]
+struct ShaderTimestamps {
+    modified: Vec<Option<SystemTime>>,
+}
+
+fn reload_shaders(
+    mut commands: Commands,
+    mut timestamps: ResMut<ShaderTimestamps>,
+    prepare: Res<PrepareRenderResources>,
+) {
+    let modified: Vec<_> = SHADERS
+        .iter()
+        .map(|path| std::fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect();
+    if timestamps.modified.is_empty() {
+        timestamps.modified = modified;
+        return;
+    }
+    if timestamps.modified != modified {
+        info!("shaders changed, reloading");
+        timestamps.modified = modified;
+        commands.remove_resource::<ComputeResources>();
+        commands.run_system(prepare.0);
     }
 }
 
@@ -127,7 +174,7 @@ fn prepare_render_resources(
             info!("default texture format: {:?}", default_texture_format);
             // let output = resources.surface.get_current_texture().unwrap();
             let device = &resources.device;
-            let shader = Shader::new("./test_app/shaders/compute.wgsl");
+            let shader = Shader::new(SHADERS[0]);
 
             let shader = shader.load(device);
             let target = device.create_texture(&TextureDescriptor {
@@ -223,7 +270,7 @@ fn prepare_render_resources(
                 push_constant_ranges: &[],
             });
 
-            let render_shader = Shader::new("./test_app/shaders/shader.wgsl");
+            let render_shader = Shader::new(SHADERS[1]);
             let vertex_module = render_shader.load(device);
 
             let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
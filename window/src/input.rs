@@ -0,0 +1,121 @@
+use std::{collections::HashSet, hash::Hash};
+
+use app_base::{
+    App, ApplicationEvent, Plugin,
+    event::{ElementState, MouseButton, WindowEvent},
+    events::Events,
+    keyboard::{KeyCode, PhysicalKey},
+    storage::{Res, ResMut},
+    system::scheduler::PreUpdate,
+};
+
+use crate::record::ReplayedKeys;
+
+/// Which buttons are held, and which changed this frame.
+pub struct ButtonInput<T> {
+    pressed: HashSet<T>,
+    just_pressed: HashSet<T>,
+    just_released: HashSet<T>,
+}
+
+impl<T> Default for ButtonInput<T> {
+    fn default() -> Self {
+        ButtonInput {
+            pressed: HashSet::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq + Copy> ButtonInput<T> {
+    pub fn press(&mut self, button: T) {
+        if self.pressed.insert(button) {
+            self.just_pressed.insert(button);
+        }
+    }
+
+    pub fn release(&mut self, button: T) {
+        if self.pressed.remove(&button) {
+            self.just_released.insert(button);
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        self.just_released.extend(self.pressed.drain());
+    }
+
+    pub fn pressed(&self, button: T) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn just_pressed(&self, button: T) -> bool {
+        self.just_pressed.contains(&button)
+    }
+
+    pub fn just_released(&self, button: T) -> bool {
+        self.just_released.contains(&button)
+    }
+
+    pub fn get_pressed(&self) -> impl Iterator<Item = &T> {
+        self.pressed.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+}
+
+fn update_input(
+    events: Res<Events<ApplicationEvent>>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mouse: ResMut<ButtonInput<MouseButton>>,
+    replayed: Option<Res<ReplayedKeys>>,
+) {
+    keys.clear();
+    mouse.clear();
+    for event in events.iter() {
+        let ApplicationEvent::WindowEvent { id: _, event } = event else {
+            continue;
+        };
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                if let PhysicalKey::Code(code) = event.physical_key {
+                    match event.state {
+                        ElementState::Pressed => keys.press(code),
+                        ElementState::Released => keys.release(code),
+                    }
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => mouse.press(*button),
+                ElementState::Released => mouse.release(*button),
+            },
+            // no release events arrive while the window is unfocused
+            WindowEvent::Focused(false) => {
+                keys.release_all();
+                mouse.release_all();
+            }
+            _ => {}
+        }
+    }
+    for (_, key) in replayed.iter().flat_map(|replayed| replayed.keys.iter()) {
+        if let PhysicalKey::Code(code) = key.physical_key {
+            match key.state {
+                ElementState::Pressed => keys.press(code),
+                ElementState::Released => keys.release(code),
+            }
+        }
+    }
+}
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonInput<KeyCode>>();
+        app.init_resource::<ButtonInput<MouseButton>>();
+        app.add_systems(PreUpdate, update_input);
+    }
+}
@@ -4,18 +4,27 @@ use app_base::{
     App, ApplicationEvent, Plugin,
     dpi::LogicalSize,
     event::WindowEvent,
-    event_loop::ActiveEventLoop,
+    context::EventLoopContext,
     storage::ResMut,
+    system::{commands::Commands, scheduler::{Label, PostUpdate}},
     window::{Window, WindowAttributes, WindowId},
 };
-use log::info;
+use log::{error, info};
 
 pub mod events;
+pub mod input;
+pub mod record;
 
 pub struct WindowPlugin {
     windows: WindowConfigs,
 }
 
+impl Default for WindowPlugin {
+    fn default() -> Self {
+        Self::new_many(vec![WindowConfig::default()])
+    }
+}
+
 impl WindowPlugin {
     pub fn new(title: &str, width: u32, height: u32) -> Self {
         Self {
@@ -94,12 +103,32 @@ impl Windows {
 
 impl Plugin for WindowPlugin {
     fn build(&self, app: &mut App) {
-        app.add_resource(Windows::new());
+        app.init_resource::<Windows>();
         app.add_resource(self.windows.clone());
         app.add_window_event_system(on_window_event)
     }
 }
 
+pub struct OnWindowCreated;
+
+impl Label for OnWindowCreated {
+    fn label(&self) -> &str {
+        "OnWindowCreated"
+    }
+
+    // on demand labels never run with the frame, they only need an order of their own
+    fn order(&self) -> usize {
+        PostUpdate.order() + 1
+    }
+
+    fn on_demand(&self) -> bool {
+        true
+    }
+}
+
+/// Available while [`OnWindowCreated`] runs for the window it was created for.
+pub struct CreatedWindow(pub WindowId);
+
 #[derive(Clone)]
 pub struct WindowConfig {
     pub title: String,
@@ -154,9 +183,10 @@ impl WindowConfigs {
 
 fn on_window_event(
     event: ApplicationEvent,
-    event_loop: &ActiveEventLoop,
+    event_loop: &dyn EventLoopContext,
     mut windows: ResMut<Windows>,
     mut window_configs: ResMut<WindowConfigs>,
+    mut commands: Commands,
 ) {
     match event {
         ApplicationEvent::WindowEvent { id, event } => {
@@ -164,6 +194,7 @@ fn on_window_event(
                 match event {
                     WindowEvent::CloseRequested => {
                         windows.remove_window(&id);
+                        commands.trigger(events::WindowClosedEvent::new(id));
                     }
                     _ => {}
                 }
@@ -173,15 +204,22 @@ fn on_window_event(
             info!("windows: {}", window_configs.window_configs.len());
             for config in &mut window_configs.window_configs {
                 if config.window_id.is_none() {
-                    let window = event_loop
-                        .create_window(
-                            WindowAttributes::default()
-                                .with_title(config.title.clone())
-                                .with_inner_size(LogicalSize::new(config.width, config.height)),
-                        )
-                        .expect("Failed to create window");
+                    let window = match event_loop.create_window(
+                        WindowAttributes::default()
+                            .with_title(config.title.clone())
+                            .with_inner_size(LogicalSize::new(config.width, config.height)),
+                    ) {
+                        Ok(window) => window,
+                        Err(e) => {
+                            error!("failed to create window {}: {}", config.title, e);
+                            continue;
+                        }
+                    };
                     config.window_id = Some(window.id());
-                    windows.add_window(Arc::new(window));
+                    commands.run_schedule_with(OnWindowCreated, CreatedWindow(window.id()));
+                    let window = Arc::new(window);
+                    commands.trigger(events::WindowCreatedEvent::new(window.clone()));
+                    windows.add_window(window);
                 }
             }
             let mut to_remove = vec![];
@@ -0,0 +1,1313 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use app_base::{
+    App, ApplicationEvent, Plugin,
+    context::{InjectedEvents, SuppressLiveInput},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{
+        DeviceId, ElementState, Force, Ime, KeyEvent, Modifiers, MouseButton, MouseScrollDelta, Touch, TouchPhase,
+        WindowEvent,
+    },
+    events::Events,
+    keyboard::{Key, KeyLocation, ModifiersState, NativeKey, NativeKeyCode, PhysicalKey, SmolStr},
+    storage::{Res, ResMut},
+    system::scheduler::{First, Scheduler},
+    time::Time,
+    window::{Theme, WindowId},
+};
+use log::{error, info, warn};
+
+use crate::{WindowConfigs, Windows};
+
+mod keys;
+
+const MAGIC: &[u8; 8] = b"SRAFINPT";
+const VERSION: u16 = 3;
+
+const RESUMED: u8 = 0;
+const SUSPENDED: u8 = 1;
+const QUIT: u8 = 2;
+const WINDOWS: u8 = 3;
+const KEY: u8 = 4;
+const CLOSE_REQUESTED: u8 = 16;
+const RESIZED: u8 = 17;
+const MOVED: u8 = 18;
+const FOCUSED: u8 = 19;
+const CURSOR_MOVED: u8 = 20;
+const CURSOR_ENTERED: u8 = 21;
+const CURSOR_LEFT: u8 = 22;
+const MOUSE_INPUT: u8 = 23;
+const MOUSE_WHEEL: u8 = 24;
+const REDRAW_REQUESTED: u8 = 25;
+const DESTROYED: u8 = 26;
+const OCCLUDED: u8 = 27;
+const MODIFIERS_CHANGED: u8 = 28;
+const IME: u8 = 29;
+const TOUCH: u8 = 30;
+const PINCH_GESTURE: u8 = 31;
+const PAN_GESTURE: u8 = 32;
+const DOUBLE_TAP_GESTURE: u8 = 33;
+const ROTATION_GESTURE: u8 = 34;
+const TOUCHPAD_PRESSURE: u8 = 35;
+const AXIS_MOTION: u8 = 36;
+const DROPPED_FILE: u8 = 37;
+const HOVERED_FILE: u8 = 38;
+const HOVERED_FILE_CANCELLED: u8 = 39;
+const THEME_CHANGED: u8 = 40;
+
+/// The public part of a [`KeyEvent`], winit does not let us rebuild the event itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedKey {
+    pub physical_key: PhysicalKey,
+    pub logical_key: Key,
+    pub text: Option<SmolStr>,
+    pub location: KeyLocation,
+    pub state: ElementState,
+    pub repeat: bool,
+}
+
+impl From<&KeyEvent> for RecordedKey {
+    fn from(event: &KeyEvent) -> Self {
+        RecordedKey {
+            physical_key: event.physical_key,
+            logical_key: event.logical_key.clone(),
+            text: event.text.clone(),
+            location: event.location,
+            state: event.state,
+            repeat: event.repeat,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedInput {
+    Event(ApplicationEvent),
+    /// The recorded window ids in creation order, used to map them onto the live windows.
+    Windows(Vec<WindowId>),
+    /// Keyboard input, replayed through [`ReplayedKeys`].
+    Key { id: WindowId, key: RecordedKey },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputRecord {
+    pub frame: u64,
+    pub timestamp: Duration,
+    pub input: RecordedInput,
+}
+
+/// A decoded recording. Scale factor changes and activation tokens are not
+/// part of the format, winit does not let us rebuild them.
+#[derive(Debug, Clone)]
+pub struct InputRecording {
+    pub fixed_delta: Option<Duration>,
+    pub records: Vec<InputRecord>,
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn encode_button(button: MouseButton, out: &mut Vec<u8>) {
+    let (kind, other) = match button {
+        MouseButton::Left => (0u8, 0u16),
+        MouseButton::Right => (1, 0),
+        MouseButton::Middle => (2, 0),
+        MouseButton::Back => (3, 0),
+        MouseButton::Forward => (4, 0),
+        MouseButton::Other(other) => (5, other),
+    };
+    out.push(kind);
+    out.extend_from_slice(&other.to_le_bytes());
+}
+
+fn encode_phase(phase: TouchPhase) -> u8 {
+    match phase {
+        TouchPhase::Started => 0,
+        TouchPhase::Moved => 1,
+        TouchPhase::Ended => 2,
+        TouchPhase::Cancelled => 3,
+    }
+}
+
+fn encode_bytes(bytes: &[u8], out: &mut Vec<u8>) {
+    out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(bytes);
+}
+
+fn encode_path(path: &Path, out: &mut Vec<u8>) {
+    encode_bytes(path.to_string_lossy().as_bytes(), out);
+}
+
+fn encode_optional_f64(value: Option<f64>, out: &mut Vec<u8>) {
+    out.push(value.is_some() as u8);
+    out.extend_from_slice(&value.unwrap_or_default().to_le_bytes());
+}
+
+fn encode_ime(ime: &Ime, out: &mut Vec<u8>) {
+    match ime {
+        Ime::Enabled => out.push(0),
+        Ime::Preedit(text, cursor) => {
+            out.push(1);
+            encode_bytes(text.as_bytes(), out);
+            out.push(cursor.is_some() as u8);
+            let (start, end) = cursor.unwrap_or_default();
+            out.extend_from_slice(&(start as u64).to_le_bytes());
+            out.extend_from_slice(&(end as u64).to_le_bytes());
+        }
+        Ime::Commit(text) => {
+            out.push(2);
+            encode_bytes(text.as_bytes(), out);
+        }
+        Ime::Disabled => out.push(3),
+    }
+}
+
+fn encode_touch(touch: &Touch, out: &mut Vec<u8>) {
+    out.push(encode_phase(touch.phase));
+    out.extend_from_slice(&touch.location.x.to_le_bytes());
+    out.extend_from_slice(&touch.location.y.to_le_bytes());
+    out.extend_from_slice(&touch.id.to_le_bytes());
+    match touch.force {
+        None => out.push(0),
+        Some(Force::Normalized(force)) => {
+            out.push(1);
+            out.extend_from_slice(&force.to_le_bytes());
+        }
+        Some(Force::Calibrated {
+            force,
+            max_possible_force,
+            altitude_angle,
+        }) => {
+            out.push(2);
+            out.extend_from_slice(&force.to_le_bytes());
+            out.extend_from_slice(&max_possible_force.to_le_bytes());
+            encode_optional_f64(altitude_angle, out);
+        }
+    }
+}
+
+fn encode_native_code(code: NativeKeyCode, out: &mut Vec<u8>) {
+    let (kind, value) = match code {
+        NativeKeyCode::Unidentified => (0u8, 0u32),
+        NativeKeyCode::Android(value) => (1, value),
+        NativeKeyCode::MacOS(value) => (2, u32::from(value)),
+        NativeKeyCode::Windows(value) => (3, u32::from(value)),
+        NativeKeyCode::Xkb(value) => (4, value),
+    };
+    out.push(kind);
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn encode_native_key(key: &NativeKey, out: &mut Vec<u8>) {
+    let (kind, value) = match key {
+        NativeKey::Unidentified => (0u8, 0u32),
+        NativeKey::Android(value) => (1, *value),
+        NativeKey::MacOS(value) => (2, u32::from(*value)),
+        NativeKey::Windows(value) => (3, u32::from(*value)),
+        NativeKey::Xkb(value) => (4, *value),
+        NativeKey::Web(_) => (5, 0),
+    };
+    out.push(kind);
+    out.extend_from_slice(&value.to_le_bytes());
+    if let NativeKey::Web(code) = key {
+        encode_bytes(code.as_bytes(), out);
+    }
+}
+
+/// Returns false for keys newer than the tables in `keys`.
+fn encode_key(key: &RecordedKey, out: &mut Vec<u8>) -> bool {
+    match key.physical_key {
+        PhysicalKey::Code(code) => {
+            let Some(index) = keys::encode_key_code(code) else {
+                return false;
+            };
+            out.push(0);
+            out.extend_from_slice(&index.to_le_bytes());
+        }
+        PhysicalKey::Unidentified(code) => {
+            out.push(1);
+            encode_native_code(code, out);
+        }
+    }
+    match &key.logical_key {
+        Key::Named(named) => {
+            let Some(index) = keys::encode_named_key(*named) else {
+                return false;
+            };
+            out.push(0);
+            out.extend_from_slice(&index.to_le_bytes());
+        }
+        Key::Character(text) => {
+            out.push(1);
+            encode_bytes(text.as_bytes(), out);
+        }
+        Key::Unidentified(native) => {
+            out.push(2);
+            encode_native_key(native, out);
+        }
+        Key::Dead(dead) => {
+            out.push(3);
+            out.push(dead.is_some() as u8);
+            out.extend_from_slice(&dead.map(u32::from).unwrap_or_default().to_le_bytes());
+        }
+    }
+    out.push(key.text.is_some() as u8);
+    encode_bytes(key.text.as_deref().unwrap_or_default().as_bytes(), out);
+    out.push(match key.location {
+        KeyLocation::Standard => 0,
+        KeyLocation::Left => 1,
+        KeyLocation::Right => 2,
+        KeyLocation::Numpad => 3,
+    });
+    out.push(key.state.is_pressed() as u8);
+    out.push(key.repeat as u8);
+    true
+}
+
+/// Appends the tag and payload of `input`, or returns false for events the format can't hold.
+fn encode_input(input: &RecordedInput, out: &mut Vec<u8>) -> bool {
+    let event = match input {
+        RecordedInput::Event(event) => event,
+        RecordedInput::Windows(ids) => {
+            out.push(WINDOWS);
+            out.extend_from_slice(&(ids.len() as u32).to_le_bytes());
+            for id in ids {
+                out.extend_from_slice(&u64::from(*id).to_le_bytes());
+            }
+            return true;
+        }
+        RecordedInput::Key { id, key } => {
+            out.push(KEY);
+            out.extend_from_slice(&u64::from(*id).to_le_bytes());
+            return encode_key(key, out);
+        }
+    };
+    let (id, event) = match event {
+        ApplicationEvent::Resumed => {
+            out.push(RESUMED);
+            return true;
+        }
+        ApplicationEvent::Suspended => {
+            out.push(SUSPENDED);
+            return true;
+        }
+        ApplicationEvent::Quit => {
+            out.push(QUIT);
+            return true;
+        }
+        ApplicationEvent::WindowEvent { id, event } => (*id, event),
+    };
+    let start = out.len();
+    let tag = match event {
+        WindowEvent::CloseRequested => CLOSE_REQUESTED,
+        WindowEvent::Resized(_) => RESIZED,
+        WindowEvent::Moved(_) => MOVED,
+        WindowEvent::Focused(_) => FOCUSED,
+        WindowEvent::CursorMoved { .. } => CURSOR_MOVED,
+        WindowEvent::CursorEntered { .. } => CURSOR_ENTERED,
+        WindowEvent::CursorLeft { .. } => CURSOR_LEFT,
+        WindowEvent::MouseInput { .. } => MOUSE_INPUT,
+        WindowEvent::MouseWheel { .. } => MOUSE_WHEEL,
+        WindowEvent::RedrawRequested => REDRAW_REQUESTED,
+        WindowEvent::Destroyed => DESTROYED,
+        WindowEvent::Occluded(_) => OCCLUDED,
+        WindowEvent::ModifiersChanged(_) => MODIFIERS_CHANGED,
+        WindowEvent::Ime(_) => IME,
+        WindowEvent::Touch(_) => TOUCH,
+        WindowEvent::PinchGesture { .. } => PINCH_GESTURE,
+        WindowEvent::PanGesture { .. } => PAN_GESTURE,
+        WindowEvent::DoubleTapGesture { .. } => DOUBLE_TAP_GESTURE,
+        WindowEvent::RotationGesture { .. } => ROTATION_GESTURE,
+        WindowEvent::TouchpadPressure { .. } => TOUCHPAD_PRESSURE,
+        WindowEvent::AxisMotion { .. } => AXIS_MOTION,
+        WindowEvent::DroppedFile(_) => DROPPED_FILE,
+        WindowEvent::HoveredFile(_) => HOVERED_FILE,
+        WindowEvent::HoveredFileCancelled => HOVERED_FILE_CANCELLED,
+        WindowEvent::ThemeChanged(_) => THEME_CHANGED,
+        _ => return false,
+    };
+    out.push(tag);
+    out.extend_from_slice(&u64::from(id).to_le_bytes());
+    match event {
+        WindowEvent::Resized(size) => {
+            out.extend_from_slice(&size.width.to_le_bytes());
+            out.extend_from_slice(&size.height.to_le_bytes());
+        }
+        WindowEvent::Moved(position) => {
+            out.extend_from_slice(&position.x.to_le_bytes());
+            out.extend_from_slice(&position.y.to_le_bytes());
+        }
+        WindowEvent::Focused(value) | WindowEvent::Occluded(value) => out.push(*value as u8),
+        WindowEvent::CursorMoved { position, .. } => {
+            out.extend_from_slice(&position.x.to_le_bytes());
+            out.extend_from_slice(&position.y.to_le_bytes());
+        }
+        WindowEvent::MouseInput { state, button, .. } => {
+            out.push(state.is_pressed() as u8);
+            encode_button(*button, out);
+        }
+        WindowEvent::MouseWheel { delta, phase, .. } => {
+            let (kind, x, y) = match delta {
+                MouseScrollDelta::LineDelta(x, y) => (0u8, *x as f64, *y as f64),
+                MouseScrollDelta::PixelDelta(position) => (1, position.x, position.y),
+            };
+            out.push(kind);
+            out.extend_from_slice(&x.to_le_bytes());
+            out.extend_from_slice(&y.to_le_bytes());
+            out.push(encode_phase(*phase));
+        }
+        WindowEvent::ModifiersChanged(modifiers) => {
+            out.extend_from_slice(&modifiers.state().bits().to_le_bytes());
+        }
+        WindowEvent::Ime(ime) => encode_ime(ime, out),
+        WindowEvent::Touch(touch) => encode_touch(touch, out),
+        WindowEvent::PinchGesture { delta, phase, .. } => {
+            out.extend_from_slice(&delta.to_le_bytes());
+            out.push(encode_phase(*phase));
+        }
+        WindowEvent::PanGesture { delta, phase, .. } => {
+            out.extend_from_slice(&delta.x.to_le_bytes());
+            out.extend_from_slice(&delta.y.to_le_bytes());
+            out.push(encode_phase(*phase));
+        }
+        WindowEvent::RotationGesture { delta, phase, .. } => {
+            out.extend_from_slice(&delta.to_le_bytes());
+            out.push(encode_phase(*phase));
+        }
+        WindowEvent::TouchpadPressure { pressure, stage, .. } => {
+            out.extend_from_slice(&pressure.to_le_bytes());
+            out.extend_from_slice(&stage.to_le_bytes());
+        }
+        WindowEvent::AxisMotion { axis, value, .. } => {
+            out.extend_from_slice(&axis.to_le_bytes());
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        WindowEvent::DroppedFile(path) | WindowEvent::HoveredFile(path) => encode_path(path, out),
+        WindowEvent::ThemeChanged(theme) => out.push(matches!(theme, Theme::Dark) as u8),
+        _ => {}
+    }
+    debug_assert!(out.len() > start);
+    true
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            return Err(invalid("truncated input recording"));
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> io::Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn i64(&mut self) -> io::Result<i64> {
+        Ok(i64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> io::Result<f64> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn optional_f64(&mut self) -> io::Result<Option<f64>> {
+        let present = self.bool()?;
+        let value = self.f64()?;
+        Ok(present.then_some(value))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| invalid("invalid utf-8 in input recording"))
+    }
+
+    fn button(&mut self) -> io::Result<MouseButton> {
+        let kind = self.u8()?;
+        let other = self.u16()?;
+        Ok(match kind {
+            0 => MouseButton::Left,
+            1 => MouseButton::Right,
+            2 => MouseButton::Middle,
+            3 => MouseButton::Back,
+            4 => MouseButton::Forward,
+            5 => MouseButton::Other(other),
+            _ => return Err(invalid("unknown mouse button")),
+        })
+    }
+
+    fn phase(&mut self) -> io::Result<TouchPhase> {
+        Ok(match self.u8()? {
+            0 => TouchPhase::Started,
+            1 => TouchPhase::Moved,
+            2 => TouchPhase::Ended,
+            3 => TouchPhase::Cancelled,
+            _ => return Err(invalid("unknown touch phase")),
+        })
+    }
+
+    fn ime(&mut self) -> io::Result<Ime> {
+        Ok(match self.u8()? {
+            0 => Ime::Enabled,
+            1 => {
+                let text = self.string()?;
+                let has_cursor = self.bool()?;
+                let cursor = (self.u64()? as usize, self.u64()? as usize);
+                Ime::Preedit(text, has_cursor.then_some(cursor))
+            }
+            2 => Ime::Commit(self.string()?),
+            3 => Ime::Disabled,
+            _ => return Err(invalid("unknown ime event")),
+        })
+    }
+
+    fn touch(&mut self, device_id: DeviceId) -> io::Result<Touch> {
+        let phase = self.phase()?;
+        let location = PhysicalPosition::new(self.f64()?, self.f64()?);
+        let id = self.u64()?;
+        let force = match self.u8()? {
+            0 => None,
+            1 => Some(Force::Normalized(self.f64()?)),
+            2 => Some(Force::Calibrated {
+                force: self.f64()?,
+                max_possible_force: self.f64()?,
+                altitude_angle: self.optional_f64()?,
+            }),
+            _ => return Err(invalid("unknown touch force")),
+        };
+        Ok(Touch {
+            device_id,
+            phase,
+            location,
+            force,
+            id,
+        })
+    }
+
+    fn native_code(&mut self) -> io::Result<NativeKeyCode> {
+        let kind = self.u8()?;
+        let value = self.u32()?;
+        Ok(match kind {
+            0 => NativeKeyCode::Unidentified,
+            1 => NativeKeyCode::Android(value),
+            2 => NativeKeyCode::MacOS(value as u16),
+            3 => NativeKeyCode::Windows(value as u16),
+            4 => NativeKeyCode::Xkb(value),
+            _ => return Err(invalid("unknown native key code")),
+        })
+    }
+
+    fn native_key(&mut self) -> io::Result<NativeKey> {
+        let kind = self.u8()?;
+        let value = self.u32()?;
+        Ok(match kind {
+            0 => NativeKey::Unidentified,
+            1 => NativeKey::Android(value),
+            2 => NativeKey::MacOS(value as u16),
+            3 => NativeKey::Windows(value as u16),
+            4 => NativeKey::Xkb(value),
+            5 => NativeKey::Web(self.string()?.into()),
+            _ => return Err(invalid("unknown native key")),
+        })
+    }
+
+    fn key(&mut self) -> io::Result<RecordedKey> {
+        let physical_key = match self.u8()? {
+            0 => PhysicalKey::Code(keys::decode_key_code(self.u16()?).ok_or_else(|| invalid("unknown key code"))?),
+            1 => PhysicalKey::Unidentified(self.native_code()?),
+            _ => return Err(invalid("unknown physical key")),
+        };
+        let logical_key = match self.u8()? {
+            0 => Key::Named(keys::decode_named_key(self.u16()?).ok_or_else(|| invalid("unknown named key"))?),
+            1 => Key::Character(self.string()?.into()),
+            2 => Key::Unidentified(self.native_key()?),
+            3 => {
+                let present = self.bool()?;
+                let dead = char::from_u32(self.u32()?).ok_or_else(|| invalid("invalid dead key"))?;
+                Key::Dead(present.then_some(dead))
+            }
+            _ => return Err(invalid("unknown logical key")),
+        };
+        let has_text = self.bool()?;
+        let text = self.string()?;
+        let location = match self.u8()? {
+            0 => KeyLocation::Standard,
+            1 => KeyLocation::Left,
+            2 => KeyLocation::Right,
+            3 => KeyLocation::Numpad,
+            _ => return Err(invalid("unknown key location")),
+        };
+        let state = match self.u8()? {
+            0 => ElementState::Released,
+            _ => ElementState::Pressed,
+        };
+        Ok(RecordedKey {
+            physical_key,
+            logical_key,
+            text: has_text.then(|| text.into()),
+            location,
+            state,
+            repeat: self.bool()?,
+        })
+    }
+
+    fn input(&mut self) -> io::Result<RecordedInput> {
+        let tag = self.u8()?;
+        let event = match tag {
+            RESUMED => ApplicationEvent::Resumed,
+            SUSPENDED => ApplicationEvent::Suspended,
+            QUIT => ApplicationEvent::Quit,
+            WINDOWS => {
+                let count = self.u32()?;
+                let ids = (0..count).map(|_| self.u64().map(WindowId::from)).collect::<io::Result<_>>()?;
+                return Ok(RecordedInput::Windows(ids));
+            }
+            KEY => {
+                let id = WindowId::from(self.u64()?);
+                return Ok(RecordedInput::Key { id, key: self.key()? });
+            }
+            _ => self.window_event(tag)?,
+        };
+        Ok(RecordedInput::Event(event))
+    }
+
+    fn window_event(&mut self, tag: u8) -> io::Result<ApplicationEvent> {
+        let id = WindowId::from(self.u64()?);
+        let device_id = DeviceId::dummy();
+        let event = match tag {
+            CLOSE_REQUESTED => WindowEvent::CloseRequested,
+            RESIZED => WindowEvent::Resized(PhysicalSize::new(self.u32()?, self.u32()?)),
+            MOVED => WindowEvent::Moved(PhysicalPosition::new(self.i32()?, self.i32()?)),
+            FOCUSED => WindowEvent::Focused(self.bool()?),
+            CURSOR_MOVED => WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(self.f64()?, self.f64()?),
+            },
+            CURSOR_ENTERED => WindowEvent::CursorEntered { device_id },
+            CURSOR_LEFT => WindowEvent::CursorLeft { device_id },
+            MOUSE_INPUT => {
+                let state = match self.u8()? {
+                    0 => ElementState::Released,
+                    _ => ElementState::Pressed,
+                };
+                WindowEvent::MouseInput {
+                    device_id,
+                    state,
+                    button: self.button()?,
+                }
+            }
+            MOUSE_WHEEL => {
+                let kind = self.u8()?;
+                let (x, y) = (self.f64()?, self.f64()?);
+                let delta = match kind {
+                    0 => MouseScrollDelta::LineDelta(x as f32, y as f32),
+                    _ => MouseScrollDelta::PixelDelta(PhysicalPosition::new(x, y)),
+                };
+                WindowEvent::MouseWheel {
+                    device_id,
+                    delta,
+                    phase: self.phase()?,
+                }
+            }
+            REDRAW_REQUESTED => WindowEvent::RedrawRequested,
+            DESTROYED => WindowEvent::Destroyed,
+            OCCLUDED => WindowEvent::Occluded(self.bool()?),
+            MODIFIERS_CHANGED => {
+                WindowEvent::ModifiersChanged(Modifiers::from(ModifiersState::from_bits_truncate(self.u32()?)))
+            }
+            IME => WindowEvent::Ime(self.ime()?),
+            TOUCH => WindowEvent::Touch(self.touch(device_id)?),
+            PINCH_GESTURE => WindowEvent::PinchGesture {
+                device_id,
+                delta: self.f64()?,
+                phase: self.phase()?,
+            },
+            PAN_GESTURE => WindowEvent::PanGesture {
+                device_id,
+                delta: PhysicalPosition::new(self.f32()?, self.f32()?),
+                phase: self.phase()?,
+            },
+            DOUBLE_TAP_GESTURE => WindowEvent::DoubleTapGesture { device_id },
+            ROTATION_GESTURE => WindowEvent::RotationGesture {
+                device_id,
+                delta: self.f32()?,
+                phase: self.phase()?,
+            },
+            TOUCHPAD_PRESSURE => WindowEvent::TouchpadPressure {
+                device_id,
+                pressure: self.f32()?,
+                stage: self.i64()?,
+            },
+            AXIS_MOTION => WindowEvent::AxisMotion {
+                device_id,
+                axis: self.u32()?,
+                value: self.f64()?,
+            },
+            DROPPED_FILE => WindowEvent::DroppedFile(self.string()?.into()),
+            HOVERED_FILE => WindowEvent::HoveredFile(self.string()?.into()),
+            HOVERED_FILE_CANCELLED => WindowEvent::HoveredFileCancelled,
+            THEME_CHANGED => WindowEvent::ThemeChanged(if self.bool()? { Theme::Dark } else { Theme::Light }),
+            _ => return Err(invalid("unknown event tag")),
+        };
+        Ok(ApplicationEvent::WindowEvent { id, event })
+    }
+}
+
+impl InputRecording {
+    pub fn decode(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = Reader { bytes };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not an input recording"));
+        }
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(invalid(&format!("unsupported input recording version {version}")));
+        }
+        let fixed_delta = match reader.u64()? {
+            0 => None,
+            micros => Some(Duration::from_micros(micros)),
+        };
+        let mut records = Vec::new();
+        while !reader.bytes.is_empty() {
+            let frame = reader.u64()?;
+            let timestamp = Duration::from_micros(reader.u64()?);
+            let input = reader.input()?;
+            records.push(InputRecord {
+                frame,
+                timestamp,
+                input,
+            });
+        }
+        Ok(InputRecording {
+            fixed_delta,
+            records,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::decode(&std::fs::read(path)?)
+    }
+}
+
+pub struct InputRecorder<W: Write = BufWriter<File>> {
+    writer: W,
+    start: Instant,
+    start_frame: Option<u64>,
+    windows: Vec<WindowId>,
+    buffer: Vec<u8>,
+    skipped: u64,
+}
+
+impl InputRecorder {
+    pub fn create<P: AsRef<Path>>(path: P, fixed_delta: Option<Duration>) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), fixed_delta)
+    }
+}
+
+impl<W: Write> InputRecorder<W> {
+    pub fn new(mut writer: W, fixed_delta: Option<Duration>) -> io::Result<Self> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        let micros = fixed_delta.map(|d| d.as_micros() as u64).unwrap_or(0);
+        writer.write_all(&micros.to_le_bytes())?;
+        Ok(InputRecorder {
+            writer,
+            start: Instant::now(),
+            start_frame: None,
+            windows: Vec::new(),
+            buffer: Vec::new(),
+            skipped: 0,
+        })
+    }
+
+    /// Counts frames from `frame` instead of from the first recorded input, so
+    /// frames without input at the start of the recording are kept.
+    pub fn start_at(&mut self, frame: u64) {
+        self.start_frame.get_or_insert(frame);
+    }
+
+    pub fn record(&mut self, frame: u64, event: &ApplicationEvent) -> io::Result<()> {
+        let input = match event {
+            ApplicationEvent::WindowEvent {
+                id,
+                event: WindowEvent::KeyboardInput { event, .. },
+            } => RecordedInput::Key {
+                id: *id,
+                key: RecordedKey::from(event),
+            },
+            event => RecordedInput::Event(event.clone()),
+        };
+        self.record_input(frame, &input)
+    }
+
+    /// Writes the window list when it changed, `windows` in creation order.
+    pub fn record_windows(&mut self, frame: u64, windows: Vec<WindowId>) -> io::Result<()> {
+        if windows == self.windows {
+            return Ok(());
+        }
+        self.windows = windows.clone();
+        self.record_input(frame, &RecordedInput::Windows(windows))
+    }
+
+    fn record_input(&mut self, frame: u64, input: &RecordedInput) -> io::Result<()> {
+        let start_frame = *self.start_frame.get_or_insert(frame);
+        self.buffer.clear();
+        self.buffer.extend_from_slice(&(frame - start_frame).to_le_bytes());
+        self.buffer
+            .extend_from_slice(&(self.start.elapsed().as_micros() as u64).to_le_bytes());
+        if !encode_input(input, &mut self.buffer) {
+            if self.skipped == 0 {
+                warn!("input recording skips events it can't reproduce, such as scale factor changes");
+            }
+            self.skipped += 1;
+            return Ok(());
+        }
+        self.writer.write_all(&self.buffer)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+pub struct InputReplay {
+    records: VecDeque<InputRecord>,
+    start_frame: Option<u64>,
+    recorded_windows: Vec<WindowId>,
+    keys: Vec<(WindowId, RecordedKey)>,
+}
+
+impl InputReplay {
+    pub fn new(recording: InputRecording) -> Self {
+        InputReplay {
+            records: recording.records.into(),
+            start_frame: None,
+            recorded_windows: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Events recorded for the frame after `frame`. Injected while `frame` runs they
+    /// go through the next pump, so systems see them on the frame they were recorded
+    /// on. Only the first frame of a recording arrives a frame late, it was pumped
+    /// before the replay could run. Key records are kept for [`take_keys`](Self::take_keys)
+    /// and window list records are consumed along the way.
+    pub fn take_next(&mut self, frame: u64) -> Vec<ApplicationEvent> {
+        let start_frame = *self.start_frame.get_or_insert(frame);
+        let mut due = Vec::new();
+        while let Some(record) = self.records.front() {
+            if record.frame + start_frame > frame + 1 {
+                break;
+            }
+            match self.records.pop_front().unwrap().input {
+                RecordedInput::Event(event) => due.push(event),
+                RecordedInput::Windows(windows) => self.recorded_windows = windows,
+                RecordedInput::Key { id, key } => self.keys.push((id, key)),
+            }
+        }
+        due
+    }
+
+    /// The key records the last [`take_next`](Self::take_next) found, due on the current frame.
+    pub fn take_keys(&mut self) -> Vec<(WindowId, RecordedKey)> {
+        std::mem::take(&mut self.keys)
+    }
+
+    /// The live window standing in for a recorded one: the window created at the
+    /// same position, or the main window for ids the recording never listed.
+    pub fn map_window(&self, recorded: WindowId, live: &[Option<WindowId>], main_window: Option<WindowId>) -> WindowId {
+        self.recorded_windows
+            .iter()
+            .position(|id| *id == recorded)
+            .and_then(|index| live.get(index).copied().flatten())
+            .or(main_window)
+            .unwrap_or(recorded)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.records.is_empty() && self.keys.is_empty()
+    }
+}
+
+/// The replayed key events of the current frame, which `InputPlugin` applies to
+/// `ButtonInput<KeyCode>` in place of the suppressed live keyboard input. Only
+/// present while a replay runs.
+#[derive(Default)]
+pub struct ReplayedKeys {
+    pub keys: Vec<(WindowId, RecordedKey)>,
+}
+
+fn live_windows(configs: &WindowConfigs) -> Vec<Option<WindowId>> {
+    configs.window_configs.iter().map(|config| config.window_id).collect()
+}
+
+fn record_input(
+    events: Res<Events<ApplicationEvent>>,
+    time: Res<Time>,
+    configs: Option<Res<WindowConfigs>>,
+    mut recorder: ResMut<InputRecorder>,
+) -> io::Result<()> {
+    recorder.start_at(time.frame());
+    if let Some(configs) = configs {
+        let windows = live_windows(&configs).into_iter().flatten().collect();
+        recorder.record_windows(time.frame(), windows)?;
+    }
+    for event in events.iter() {
+        recorder.record(time.frame(), event)?;
+    }
+    recorder.flush()
+}
+
+// the events go through the real event loop during the next pump, so window
+// event systems can create windows or exit just like with live input. Keys
+// can't be turned back into window events and are handed over in ReplayedKeys.
+fn replay_input(scheduler: &mut Scheduler) {
+    let Some(frame) = scheduler.get_resource::<Time>().map(|time| time.frame()) else {
+        return;
+    };
+    let live = scheduler
+        .get_resource::<WindowConfigs>()
+        .map(|configs| live_windows(&configs))
+        .unwrap_or_default();
+    let main_window = scheduler
+        .get_resource::<Windows>()
+        .and_then(|windows| windows.main_window);
+    let (keys, due, finished) = match scheduler.get_resource_mut::<InputReplay>() {
+        Some(mut replay) => {
+            let keys: Vec<_> = replay
+                .take_keys()
+                .into_iter()
+                .map(|(id, key)| (replay.map_window(id, &live, main_window), key))
+                .collect();
+            let due: Vec<_> = replay
+                .take_next(frame)
+                .into_iter()
+                .filter_map(|event| match event {
+                    // lifecycle events keep coming from the real event loop
+                    ApplicationEvent::Resumed | ApplicationEvent::Suspended => None,
+                    ApplicationEvent::WindowEvent { id, event } => Some(ApplicationEvent::WindowEvent {
+                        id: replay.map_window(id, &live, main_window),
+                        event,
+                    }),
+                    event => Some(event),
+                })
+                .collect();
+            let finished = replay.is_finished() && keys.is_empty();
+            (keys, due, finished)
+        }
+        None => return,
+    };
+    scheduler.init_resource::<ReplayedKeys>();
+    if let Some(mut replayed) = scheduler.get_resource_mut::<ReplayedKeys>() {
+        replayed.keys = keys;
+    }
+    scheduler.init_resource::<InjectedEvents>();
+    if let Some(mut injected) = scheduler.get_resource_mut::<InjectedEvents>() {
+        for event in due {
+            injected.push(event);
+        }
+    }
+    if finished {
+        scheduler.remove_resource::<InputReplay>();
+        scheduler.remove_resource::<SuppressLiveInput>();
+        scheduler.remove_resource::<ReplayedKeys>();
+        info!("input replay finished at frame {}", frame);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum InputRecordingMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// Records or replays input in [`First`], before `InputPlugin` reads it in `PreUpdate`.
+pub struct InputRecordingPlugin {
+    mode: InputRecordingMode,
+    fixed_delta: Option<Duration>,
+}
+
+impl InputRecordingPlugin {
+    pub fn record<P: Into<PathBuf>>(path: P) -> Self {
+        InputRecordingPlugin {
+            mode: InputRecordingMode::Record(path.into()),
+            fixed_delta: None,
+        }
+    }
+
+    pub fn replay<P: Into<PathBuf>>(path: P) -> Self {
+        InputRecordingPlugin {
+            mode: InputRecordingMode::Replay(path.into()),
+            fixed_delta: None,
+        }
+    }
+
+    /// Only used while recording, a replay takes the timestep stored in the file.
+    pub fn with_fixed_delta(mut self, delta: Duration) -> Self {
+        self.fixed_delta = Some(delta);
+        self
+    }
+}
+
+impl Plugin for InputRecordingPlugin {
+    fn build(&self, app: &mut App) {
+        match &self.mode {
+            InputRecordingMode::Record(path) => {
+                let recorder = match InputRecorder::create(path, self.fixed_delta) {
+                    Ok(recorder) => recorder,
+                    Err(e) => {
+                        error!("failed to create input recording {}: {}", path.display(), e);
+                        return;
+                    }
+                };
+                if let Some(delta) = self.fixed_delta {
+                    app.add_resource(Time::new().with_fixed_delta(delta));
+                }
+                app.add_resource(recorder);
+                app.add_systems(First, record_input);
+            }
+            InputRecordingMode::Replay(path) => {
+                let recording = match InputRecording::load(path) {
+                    Ok(recording) => recording,
+                    Err(e) => {
+                        error!("failed to load input recording {}: {}", path.display(), e);
+                        return;
+                    }
+                };
+                match recording.fixed_delta {
+                    Some(delta) => app.add_resource(Time::new().with_fixed_delta(delta)),
+                    None => warn!("{} was recorded without a fixed timestep, replay timing may differ", path.display()),
+                }
+                app.add_resource(InputReplay::new(recording));
+                app.add_resource(SuppressLiveInput::default());
+                app.add_systems(First, replay_input);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::PathBuf,
+        sync::{Arc, Mutex},
+    };
+
+    use app_base::{
+        context::MockEventLoop,
+        keyboard::{KeyCode, NamedKey},
+        system::scheduler::Update,
+    };
+
+    use super::*;
+    use crate::input::{ButtonInput, InputPlugin};
+
+    fn window_event(id: u64, event: WindowEvent) -> ApplicationEvent {
+        ApplicationEvent::WindowEvent {
+            id: WindowId::from(id),
+            event,
+        }
+    }
+
+    fn events() -> Vec<ApplicationEvent> {
+        let device_id = DeviceId::dummy();
+        vec![
+            ApplicationEvent::Resumed,
+            window_event(1, WindowEvent::Resized(PhysicalSize::new(640, 480))),
+            window_event(1, WindowEvent::Moved(PhysicalPosition::new(-4, 12))),
+            window_event(1, WindowEvent::Focused(true)),
+            window_event(2, WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(1.5, 2.25),
+            }),
+            window_event(2, WindowEvent::MouseInput {
+                device_id,
+                state: ElementState::Pressed,
+                button: MouseButton::Other(9),
+            }),
+            window_event(2, WindowEvent::MouseWheel {
+                device_id,
+                delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.5, -3.0)),
+                phase: TouchPhase::Moved,
+            }),
+            window_event(1, WindowEvent::ModifiersChanged(Modifiers::from(
+                ModifiersState::SHIFT | ModifiersState::CONTROL,
+            ))),
+            window_event(1, WindowEvent::Ime(Ime::Preedit("かな".to_string(), Some((0, 3))))),
+            window_event(1, WindowEvent::Ime(Ime::Commit("\"done\"".to_string()))),
+            window_event(1, WindowEvent::Touch(Touch {
+                device_id,
+                phase: TouchPhase::Started,
+                location: PhysicalPosition::new(10.0, 20.0),
+                force: Some(Force::Calibrated {
+                    force: 0.5,
+                    max_possible_force: 2.0,
+                    altitude_angle: None,
+                }),
+                id: 42,
+            })),
+            window_event(1, WindowEvent::PanGesture {
+                device_id,
+                delta: PhysicalPosition::new(1.0, -1.0),
+                phase: TouchPhase::Ended,
+            }),
+            window_event(1, WindowEvent::TouchpadPressure {
+                device_id,
+                pressure: 0.75,
+                stage: -2,
+            }),
+            window_event(1, WindowEvent::DroppedFile(PathBuf::from("/tmp/dropped file.txt"))),
+            window_event(1, WindowEvent::ThemeChanged(Theme::Dark)),
+            window_event(1, WindowEvent::CloseRequested),
+            ApplicationEvent::Quit,
+        ]
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut recorder = InputRecorder::new(Vec::new(), Some(Duration::from_micros(16_667))).unwrap();
+        recorder.record_windows(5, vec![WindowId::from(1), WindowId::from(2)]).unwrap();
+        for (i, event) in events().iter().enumerate() {
+            recorder.record(5 + i as u64 / 4, event).unwrap();
+        }
+        assert_eq!(recorder.skipped(), 0);
+        let recording = InputRecording::decode(&recorder.into_inner()).unwrap();
+
+        assert_eq!(recording.fixed_delta, Some(Duration::from_micros(16_667)));
+        assert_eq!(
+            recording.records[0].input,
+            RecordedInput::Windows(vec![WindowId::from(1), WindowId::from(2)])
+        );
+        let decoded: Vec<_> = recording.records[1..].iter().map(|record| record.input.clone()).collect();
+        let expected: Vec<_> = events().into_iter().map(RecordedInput::Event).collect();
+        assert_eq!(decoded, expected);
+        let frames: Vec<_> = recording.records[1..].iter().map(|record| record.frame).collect();
+        assert_eq!(frames, (0..expected.len() as u64).map(|i| i / 4).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn rejects_truncated_and_foreign_data() {
+        let mut recorder = InputRecorder::new(Vec::new(), None).unwrap();
+        recorder.record(0, &events()[1]).unwrap();
+        let bytes = recorder.into_inner();
+        assert!(InputRecording::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(InputRecording::decode(b"NOTINPUT\x02\x00").is_err());
+    }
+
+    #[test]
+    fn maps_recorded_windows_by_creation_order() {
+        let mut recorder = InputRecorder::new(Vec::new(), None).unwrap();
+        recorder.record_windows(0, vec![WindowId::from(10), WindowId::from(20)]).unwrap();
+        recorder.record(0, &window_event(20, WindowEvent::CloseRequested)).unwrap();
+        let recording = InputRecording::decode(&recorder.into_inner()).unwrap();
+
+        let mut replay = InputReplay::new(recording);
+        let due = replay.take_next(3);
+        assert_eq!(due.len(), 1);
+        let live = [Some(WindowId::from(100)), Some(WindowId::from(200))];
+        let main = Some(WindowId::from(100));
+        assert_eq!(replay.map_window(WindowId::from(20), &live, main), WindowId::from(200));
+        assert_eq!(replay.map_window(WindowId::from(10), &live, main), WindowId::from(100));
+        assert_eq!(replay.map_window(WindowId::from(30), &live, main), WindowId::from(100));
+        assert!(replay.is_finished());
+    }
+
+    fn key(physical_key: PhysicalKey, logical_key: Key, state: ElementState) -> RecordedKey {
+        RecordedKey {
+            physical_key,
+            logical_key,
+            text: None,
+            location: KeyLocation::Standard,
+            state,
+            repeat: false,
+        }
+    }
+
+    #[test]
+    fn round_trips_keys() {
+        let keys = vec![
+            RecordedKey {
+                text: Some("a".into()),
+                ..key(PhysicalKey::Code(KeyCode::KeyA), Key::Character("a".into()), ElementState::Pressed)
+            },
+            RecordedKey {
+                location: KeyLocation::Left,
+                repeat: true,
+                ..key(PhysicalKey::Code(KeyCode::ShiftLeft), Key::Named(NamedKey::Shift), ElementState::Released)
+            },
+            key(
+                PhysicalKey::Unidentified(NativeKeyCode::Xkb(42)),
+                Key::Unidentified(NativeKey::Web("Unknown".into())),
+                ElementState::Pressed,
+            ),
+            key(PhysicalKey::Code(KeyCode::F35), Key::Dead(Some('\u{b4}')), ElementState::Pressed),
+        ];
+        let mut recorder = InputRecorder::new(Vec::new(), None).unwrap();
+        for key in &keys {
+            recorder
+                .record_input(0, &RecordedInput::Key {
+                    id: WindowId::from(1),
+                    key: key.clone(),
+                })
+                .unwrap();
+        }
+        assert_eq!(recorder.skipped(), 0);
+        let recording = InputRecording::decode(&recorder.into_inner()).unwrap();
+        let decoded: Vec<_> = recording.records.into_iter().map(|record| record.input).collect();
+        let expected: Vec<_> = keys
+            .into_iter()
+            .map(|key| RecordedInput::Key {
+                id: WindowId::from(1),
+                key,
+            })
+            .collect();
+        assert_eq!(decoded, expected);
+    }
+
+    struct Seen<T>(Arc<Mutex<Vec<T>>>);
+
+    /// The frame, then whether `KeyA` is pressed, just pressed and just released.
+    type KeyState = (u64, bool, bool, bool);
+
+    fn observe_events(events: Res<Events<ApplicationEvent>>, time: Res<Time>, seen: Res<Seen<(u64, ApplicationEvent)>>) {
+        let mut seen = seen.0.lock().unwrap();
+        seen.extend(events.iter().map(|event| (time.frame(), event.clone())));
+    }
+
+    fn observe_keys(keys: Res<ButtonInput<KeyCode>>, time: Res<Time>, seen: Res<Seen<KeyState>>) {
+        seen.0.lock().unwrap().push((
+            time.frame(),
+            keys.pressed(KeyCode::KeyA),
+            keys.just_pressed(KeyCode::KeyA),
+            keys.just_released(KeyCode::KeyA),
+        ));
+    }
+
+    fn temp_recording(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}-{}.input", name, std::process::id()))
+    }
+
+    /// Runs frames 1 to `frames` like `App::run`, sending each live event before its frame.
+    fn run_frames(app: &mut App, frames: u64, live: &[(u64, ApplicationEvent)]) {
+        let event_loop = MockEventLoop::new();
+        for frame in 1..=frames {
+            app.run_injected_events(&event_loop);
+            for (_, event) in live.iter().filter(|(at, _)| *at == frame) {
+                event_loop.send(app, event.clone());
+            }
+            app.update();
+        }
+    }
+
+    #[test]
+    fn replays_on_the_recorded_frame() {
+        let path = temp_recording("replays_on_the_recorded_frame");
+        let device_id = DeviceId::dummy();
+        let live = vec![
+            (2, window_event(1, WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(3.0, 4.0),
+            })),
+            (4, window_event(1, WindowEvent::MouseInput {
+                device_id,
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+            })),
+            (4, window_event(1, WindowEvent::CursorLeft { device_id })),
+        ];
+
+        let recorded: Arc<Mutex<Vec<(u64, ApplicationEvent)>>> = Arc::default();
+        let mut app = App::new();
+        app.add_plugin(InputRecordingPlugin::record(&path).with_fixed_delta(Duration::from_millis(16)));
+        app.add_resource(Seen(recorded.clone()));
+        app.add_systems(Update, observe_events);
+        run_frames(&mut app, 5, &live);
+        drop(app);
+        assert_eq!(*recorded.lock().unwrap(), live);
+
+        let replayed: Arc<Mutex<Vec<(u64, ApplicationEvent)>>> = Arc::default();
+        let mut app = App::new();
+        app.add_plugin(InputRecordingPlugin::replay(&path));
+        app.add_resource(Seen(replayed.clone()));
+        app.add_systems(Update, observe_events);
+        run_frames(&mut app, 5, &[]);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(*replayed.lock().unwrap(), live);
+    }
+
+    #[test]
+    fn replays_keys_into_button_input() {
+        let path = temp_recording("replays_keys_into_button_input");
+        let mut recorder = InputRecorder::create(&path, None).unwrap();
+        recorder.start_at(1);
+        for (frame, state) in [(2, ElementState::Pressed), (3, ElementState::Released)] {
+            let key = key(PhysicalKey::Code(KeyCode::KeyA), Key::Character("a".into()), state);
+            recorder
+                .record_input(frame, &RecordedInput::Key {
+                    id: WindowId::from(1),
+                    key,
+                })
+                .unwrap();
+        }
+        recorder.flush().unwrap();
+        drop(recorder);
+
+        let seen: Arc<Mutex<Vec<KeyState>>> = Arc::default();
+        let mut app = App::new();
+        app.add_plugin(InputPlugin);
+        app.add_plugin(InputRecordingPlugin::replay(&path));
+        app.add_resource(Seen(seen.clone()));
+        app.add_systems(Update, observe_keys);
+        run_frames(&mut app, 4, &[]);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![
+            (1, false, false, false),
+            (2, true, true, false),
+            (3, false, false, true),
+            (4, false, false, false),
+        ]);
+    }
+}